@@ -1,227 +1,1059 @@
-use crate::{collision::*, render::Render, Direction, Fruit, GameStatus, Snake, Wall};
+use crate::{
+    assets,
+    audio::{Mixer, Sound},
+    consts,
+    editor::{Editor, Tool},
+    event::{GameEvent, Subscriber},
+    game::{Game, Setup},
+    gamepad::{Gamepads, PadAction},
+    hud::HudButton,
+    layout::Layout,
+    level::Level,
+    locale::{self, trf},
+    menu::{Menu, MenuAction, MenuPage},
+    render::{Flashes, Render},
+    replay::Replay,
+    save::SavedGame,
+    score::ScoreTables,
+    settings::{Setting, Settings},
+    stats::GameStats,
+    theme::{Theme, Themes},
+    tileset::Tileset,
+    Direction, Error, GameMode, GameStatus, Snake,
+};
 use glutin_window::GlutinWindow as Window;
 use opengl_graphics::{GlGraphics, OpenGL, GlyphCache,TextureSettings,Filter};
 use piston::event_loop::{EventLoop, EventSettings, Events};
 use piston::input::*;
 use piston::{UpdateArgs, WindowSettings};
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 创建窗口时依次尝试的OpenGL版本，老旧的显卡和虚拟机可能只支持2.1
+const OPENGL_VERSIONS: [OpenGL; 3] = [OpenGL::V3_2, OpenGL::V3_0, OpenGL::V2_1];
 
 /// 应用程序主体结构体
 pub struct App<'a> {
-    pub game_status: GameStatus, // 游戏状态机
-    pub circus: [u32; 2],        // 移动空间
-    update_time: f64,            // 记录一次更新后的时间
-    pub score: u32,              // 记录玩家的分数
-    pub board_wall: Wall,        // 边界
-    pub walls: Vec<Wall>,        // 随机生成的墙壁
-    pub fruit: Fruit,            // 食物
-    pub snake: Snake,            // 蛇蛇
+    pub game_status: GameStatus,           // 游戏状态机
+    pub circus: [u32; 2],                  // 移动空间
+    pub game: Game,                        // 当前的一局
     pub glyph_cache:GlyphCache<'a>,
+    pub menu: Menu,                        // 菜单
+    pub mode: GameMode,                    // 当前的游戏模式
+    pub level: Level,                      // 当前的关卡
+    pub high_scores: ScoreTables,          // 每种模式的高分榜
+    pub high_score_view: GameMode,         // 高分榜页面正在显示的模式
+    pub cursor: [f64; 2],                  // 鼠标在窗口中的位置
+    drag_start: Option<[f64; 2]>,          // 游戏中按住鼠标的起点，用来识别滑动手势
+    swiped: bool,                          // 这次按住鼠标之后是否已经滑动过
+    gamepads: Gamepads,                    // 所有手柄的输入
+    pub window_size: [f64; 2],             // 上一次渲染时的窗口尺寸
+    pub layout: Layout,                    // 上一次渲染时的布局
+    pub result: Option<u32>,               // 本局计入高分榜的成绩
+    pub countdown: f64,                    // 继续游戏前剩余的倒计时
+    pub stats: GameStats,                  // 本局的统计数据
+    pub replay: Replay,                    // 本局的录像
+    pub settings: Settings,                // 玩家设置
+    pub binding: Option<Setting>,          // 正在等待新按键的按键绑定
+    pub themes: Themes,                    // 所有可用的主题
+    pub theme: Theme,                      // 当前的主题
+    pub tileset: Option<Tileset>,          // 图块集，为空时用矩形绘制
+    pub mixer: Mixer,                      // 混音器
+    pub editor: Editor,                    // 关卡编辑器
+    pub flashes: Flashes,                  // 渲染器订阅事件后显示的闪光
+    subscribers: Vec<Box<dyn Subscriber>>, // 外部的事件订阅者
 }
 
 impl App<'_> {
-    /// 建立新的App实例
-    pub fn new(horizontal_block_num: u32, vertical_block_num: u32) -> Self {
-        let circus = [horizontal_block_num, vertical_block_num];
+    /// 按玩家设置建立新的App实例，字体无法使用时返回错误
+    pub fn new(settings: Settings) -> Result<Self, Error> {
+        let circus = settings.board_size;
+        let level = Level::Random;
+        let themes = Themes::load();
+        let theme = themes.get(&settings.theme).clone();
+        let mixer = Mixer::new(&settings);
+        let editor = Editor::new(circus, Editor::default_path());
+        let seed = rand::thread_rng().gen();
+        let game = build_game(GameMode::Classic, level, &settings, &editor, seed);
+        let high_scores = ScoreTables::load().unwrap_or_else(|e| {
+            eprintln!("{}, starting with empty high scores", e);
+            ScoreTables::new()
+        });
+
+        // 为了能够渲染文字，需要按界面语言读取字体缓存
+        locale::set(settings.language);
+        let glyph_cache = load_glyph_cache()?;
+
+        let mut app = App {
+            game_status: GameStatus::MENU,
+            circus,
+            game,
+            glyph_cache,
+            menu: Menu::new(MenuPage::Title),
+            mode: GameMode::Classic,
+            level,
+            high_scores,
+            high_score_view: GameMode::Classic,
+            cursor: [0.0, 0.0],
+            drag_start: None,
+            swiped: false,
+            gamepads: Gamepads::new(),
+            window_size: [640.0, 480.0],
+            layout: Layout::new(&circus, [640.0, 480.0], 1.0, settings.hud.len()),
+            result: None,
+            countdown: 0.0,
+            stats: GameStats::new(),
+            replay: Replay::default(),
+            settings,
+            binding: None,
+            themes,
+            theme,
+            tileset: None,
+            mixer,
+            editor,
+            flashes: Flashes::new(),
+            subscribers: Vec::new(),
+        };
+        app.menu.can_continue = SavedGame::exists();
+        app.paint();
+
+        Ok(app)
+    }
 
-        let mut walls = Vec::<Wall>::new();
-        let walls_num = rand::thread_rng().gen_range(1, 10);
-        for _ in 0..walls_num {
-            let brick_num = rand::thread_rng().gen_range(5, 10);
+    /// 按当前的模式、关卡和设置开始新的一局，保留菜单、高分榜等状态
+    fn new_game(&mut self) {
+        self.discard_save();
+        let seed = self
+            .settings
+            .seed
+            .unwrap_or_else(|| rand::thread_rng().gen());
+        self.game = build_game(self.mode, self.level, &self.settings, &self.editor, seed);
+        self.circus = self.game.circus;
+        self.result = None;
+        self.paint();
+        self.stats = GameStats::new();
+        self.start_replay();
+        self.game_status = GameStatus::GAMING;
+        self.dispatch();
+    }
 
-            walls.push(Wall::randnew(Some(brick_num), &circus));
+    /// 订阅游戏事件
+    pub fn subscribe(&mut self, subscriber: Box<dyn Subscriber>) {
+        self.subscribers.push(subscriber);
+    }
+
+    /// 取出本局排队的事件发送给所有订阅者，返回是否有事件
+    fn dispatch(&mut self) -> bool {
+        let events = self.game.take_events();
+        for event in events.iter() {
+            self.emit(event);
         }
 
-        // 为了能够渲染文字，需要读取字体缓存
-        let texture_settings = TextureSettings::new().filter(Filter::Nearest);
-        let glyph_cache = GlyphCache::new("assets/Roboto-Regular.ttf", (), texture_settings)
-            .expect("Error unwrapping fonts");
+        !events.is_empty()
+    }
 
-        App {
-            game_status: GameStatus::GAMING,
-            circus,
-            update_time: 0.0,
-            score: 0,
-            board_wall: Wall::board_wall(&circus),
-            walls,
-            fruit: Fruit::randnew(horizontal_block_num, vertical_block_num),
-            snake: Snake::new(horizontal_block_num, vertical_block_num),
-            glyph_cache,
+    /// 把事件发送给所有订阅者
+    fn emit(&mut self, event: &GameEvent) {
+        self.mixer.notify(event);
+        self.stats.notify(event);
+        self.replay.notify(event);
+        self.flashes.notify(event);
+
+        for subscriber in self.subscribers.iter_mut() {
+            subscriber.notify(event);
+        }
+    }
+
+    /// 按当前局面开始录像
+    fn start_replay(&mut self) {
+        let game = &self.game;
+        let walls = game
+            .walls
+            .iter()
+            .flat_map(|wall| wall.bricks.iter())
+            .map(|brick| [brick.pos_x, brick.pos_y])
+            .collect();
+        let snake = std::iter::once(&game.snake.head)
+            .chain(game.snake.body.iter())
+            .map(|block| [block.pos_x, block.pos_y])
+            .collect();
+
+        self.replay = Replay {
+            mode: game.mode,
+            portals: game
+                .portals
+                .iter()
+                .map(|portal| portal.ends.each_ref().map(|end| [end.pos_x, end.pos_y]))
+                .collect(),
+            dynamic_walls: game.dynamic_walls.clone(),
+            ..Replay::new(game.circus, walls, snake)
+        };
+    }
+
+    /// 暂停游戏并打开暂停菜单
+    fn pause(&mut self) {
+        self.menu.reset(MenuPage::Pause);
+        self.game_status = GameStatus::TIMEOUT;
+        self.emit(&GameEvent::Paused);
+    }
+
+    /// 是否有正在进行的一局
+    fn is_playing(&self) -> bool {
+        matches!(
+            self.game_status,
+            GameStatus::GAMING | GameStatus::TIMEOUT | GameStatus::RESUMING
+        )
+    }
+
+    /// 把正在进行的一局写入存档
+    fn save_game(&mut self) {
+        let saved = SavedGame {
+            level: self.level,
+            game: self.game.clone(),
+            stats: self.stats.clone(),
+            replay: self.replay.clone(),
+        };
+
+        self.menu.message = Some(match saved.save() {
+            Ok(path) => trf("Game saved to {}", &[&path.display()]),
+            Err(e) => trf("Failed to save game: {}", &[&e]),
+        });
+        self.menu.can_continue = SavedGame::exists();
+    }
+
+    /// 读取存档，在暂停菜单中恢复存档时的局面，存档读取之后就被删除
+    fn continue_game(&mut self) {
+        let saved = match SavedGame::load() {
+            Ok(saved) => saved,
+            Err(e) => {
+                self.menu.message = Some(trf("Failed to load saved game: {}", &[&e]));
+                self.menu.can_continue = false;
+                return;
+            }
+        };
+        self.discard_save();
+
+        self.mode = saved.game.mode;
+        self.level = saved.level;
+        self.circus = saved.game.circus;
+        self.game = saved.game;
+        self.result = None;
+        self.stats = saved.stats;
+        self.replay = saved.replay;
+        self.paint();
+
+        self.pause();
+    }
+
+    // 删除存档，存档中的一局被继续、重新开始或者结束之后就不能再继续了
+    // 否则会回到已经结束的一局，成绩也会被再次记录
+    fn discard_save(&mut self) {
+        if let Err(e) = SavedGame::remove() {
+            eprintln!("Failed to remove saved game: {}", e);
+        }
+        self.menu.can_continue = false;
+    }
+
+    /// 退出程序，有正在进行的一局时先写入存档
+    fn quit(&mut self) {
+        if self.is_playing() {
+            self.save_game();
+        }
+        self.game_status = GameStatus::QUIT;
+    }
+
+    /// 继续游戏，先倒计时让玩家做好准备
+    fn resume(&mut self) {
+        self.countdown = consts::RESUME_COUNTDOWN;
+        self.game_status = GameStatus::RESUMING;
+    }
+
+    // 倒计时中暂停，回到暂停菜单，游戏还没有继续所以不发出暂停事件
+    fn cancel_resume(&mut self) {
+        self.menu.reset(MenuPage::Pause);
+        self.game_status = GameStatus::TIMEOUT;
+    }
+
+    // 窗口失去焦点时自动暂停
+    fn focus(&mut self, focused: bool) {
+        match self.game_status {
+            GameStatus::GAMING if !focused => self.pause(),
+            GameStatus::RESUMING if !focused => self.cancel_resume(),
+            _ => (),
+        }
+    }
+
+    /// 按当前主题给所有实体上色
+    fn paint(&mut self) {
+        let game = &mut self.game;
+        game.snake.paint(&self.theme);
+        game.fruit
+            .paint(self.theme.fruit_color(game.fruits as usize));
+        if let Some(power_up) = &mut game.power_up {
+            power_up.paint(self.theme.power_up_color(power_up.kind));
+        }
+        game.board_wall.paint(self.theme.border);
+        for wall in game.walls.iter_mut() {
+            wall.paint(self.theme.wall);
+        }
+        for wall in game.dynamic_walls.iter_mut() {
+            wall.paint(self.theme.wall);
+        }
+        for (i, portal) in game.portals.iter_mut().enumerate() {
+            portal.paint(self.theme.portal_color(i));
+        }
+    }
+
+    /// 调整设置项，立即生效并写入设置文件
+    fn change_setting(&mut self, setting: Setting, step: i32) {
+        match setting {
+            Setting::Theme => {
+                self.theme = self.themes.cycle(&self.settings.theme, step).clone();
+                self.settings.theme = self.theme.name.clone();
+            }
+            Setting::Language => {
+                self.settings.adjust(setting, step);
+                self.apply_language();
+            }
+            Setting::Sprites => {
+                self.settings.adjust(setting, step);
+                self.load_tileset();
+            }
+            // 从随机改为固定种子时使用最近一局的种子，可以重玩同一个关卡
+            Setting::Seed if self.settings.seed.is_none() => {
+                self.settings.seed = Some(self.game.seed)
+            }
+            _ => self.settings.adjust(setting, step),
+        }
+        self.apply_settings();
+    }
+
+    /// 按设置读取图块集，读取失败时退回到矩形绘制
+    /// 玩家指定的文件优先，其次是查找目录中的文件，默认的图块集嵌入在程序中
+    /// 需要OpenGL上下文，所以只能在窗口创建之后调用
+    fn load_tileset(&mut self) {
+        self.tileset = match &self.settings.tileset {
+            Some(path) => match read_tileset(path).and_then(|bytes| Tileset::from_bytes(&bytes)) {
+                Ok(tileset) => Some(tileset),
+                Err(e) => {
+                    eprintln!("Failed to load tileset {}: {}", path, e);
+                    None
+                }
+            },
+            None => None,
+        };
+    }
+
+    /// 切换界面语言，中文需要换用能显示汉字的字体
+    fn apply_language(&mut self) {
+        locale::set(self.settings.language);
+
+        match load_glyph_cache() {
+            Ok(glyph_cache) => self.glyph_cache = glyph_cache,
+            Err(e) => eprintln!("Failed to switch font: {}", e),
+        }
+    }
+
+    /// 让修改后的设置立即生效，并写入设置文件
+    /// 移动空间、速度和墙壁密度在下一局生效
+    fn apply_settings(&mut self) {
+        self.paint();
+        self.mixer.apply(&self.settings);
+
+        if let Err(e) = self.settings.save() {
+            eprintln!("Failed to save settings: {}", e);
+        }
+    }
+
+    /// 本局结束后把成绩记入当前模式的高分榜并保存，试玩自定义关卡的成绩不计入高分榜
+    /// 目标模式记录达到目标长度的用时，没有达到时不记录
+    fn end_game(&mut self) {
+        self.result = match self.game.mode {
+            GameMode::Target if self.game.snake.length() < consts::TARGET_LENGTH => None,
+            GameMode::Target => Some(((self.game.elapsed * 10.0).round() as u32).max(1)),
+            _ => Some(self.game.score),
+        };
+
+        let table = self.high_scores.get_mut(self.game.mode);
+        self.stats.previous_best = table.best();
+        if let (Some(result), false) = (self.result, self.level == Level::Custom) {
+            if table.insert(result) {
+                if let Err(e) = self.high_scores.save() {
+                    eprintln!("Failed to save high scores: {}", e);
+                }
+            }
         }
+
+        self.discard_save();
+        self.menu.reset(MenuPage::GameOver);
+        self.game_status = GameStatus::GAMEOVER;
     }
 
     /// 理论计算更新主函数
     fn update(&mut self, args: &UpdateArgs) {
+        self.flashes.update(args.dt);
+
         match self.game_status {
             // 游戏中
             GameStatus::GAMING => {
-                // 积累下一次更新的时间
-                self.update_time += args.dt;
-
-                // 我们以固定的时间间隔更新游戏的逻辑
-                if self.update_time >= (1.0 / self.snake.velocity) {
-                    // 解锁方向
-                    self.snake.direction_lock = false;
-
-                    // 碰撞检测
-                    match self.is_collision() {
-                        Collited::WithFruit => self.growth_action(),
-                        Collited::NoCollision => (),
-                        _ => self.game_status = GameStatus::GAMEOVER,
-                    }
-
-                    // 移动蛇
-                    self.snake.moving();
+                // 推进本局，有事件发生时局面发生了变化，按主题重新上色
+                self.game.update(args.dt);
+                if self.dispatch() {
+                    self.paint();
+                }
+                if self.game.status == GameStatus::GAMEOVER {
+                    self.end_game();
+                }
+            }
+            // 继续游戏前倒计时，每过一秒提示一次，结束时继续游戏
+            GameStatus::RESUMING => {
+                let before = self.countdown.ceil();
+                self.countdown -= args.dt;
 
-                    // 初始化时间
-                    self.update_time = 0.0;
+                if self.countdown <= 0.0 {
+                    self.game_status = GameStatus::GAMING;
+                    self.emit(&GameEvent::Resumed);
+                } else if self.countdown.ceil() < before {
+                    self.mixer.play(Sound::Menu);
                 }
             }
             // 重启
-            GameStatus::RESTART => {
-                let pristine = App::new(self.circus[0], self.circus[1]);
-                *self = pristine;
-                return;
-            }
+            GameStatus::RESTART => self.new_game(),
             // 如果游戏结束或暂停就不继续更新了
             _ => (),
         }
     }
 
-    // 蛇身加长的操作方法
-    fn growth_action(&mut self) {
-        // 调用蛇的方法
-        self.snake.growth_action();
-        // 仅选择一个随机位置，也可能在蛇上
-        self.fruit = Fruit::randnew(self.circus[0], self.circus[1]);
-        // 增加分数
-        self.score += 1;
+    // 按键判定
+    fn press(&mut self, button: &Button) {
+        match *button {
+            Button::Keyboard(key) => self.key_press(key),
+            Button::Mouse(MouseButton::Left) if self.game_status == GameStatus::EDITOR => {
+                self.editor_mouse_press(self.editor.tool)
+            }
+            Button::Mouse(MouseButton::Right) if self.game_status == GameStatus::EDITOR => {
+                self.editor_mouse_press(Tool::Erase)
+            }
+            Button::Mouse(MouseButton::Left) => self.mouse_click(),
+            _ => {}
+        }
     }
 
-    // 碰撞检测
-    fn is_collision(&mut self) -> Collited {
-        // 碰撞到了身体？
-        if self.snake.is_colliting_with_self() {
-            return Collited::WithSnake;
+    // 读取手柄产生的操作
+    fn poll_gamepads(&mut self) {
+        for (id, action) in self.gamepads.poll(&self.settings.gamepad) {
+            self.pad_action(id, action);
         }
+    }
+
+    // 执行手柄操作，只接受分配给玩家的手柄，菜单中的操作与对应的按键相同
+    // 目前只有一条蛇，由玩家1控制，其他玩家的手柄可以暂停和操作菜单
+    fn pad_action(&mut self, id: usize, action: PadAction) {
+        let player = match self.settings.gamepad.player(id) {
+            Some(player) if self.binding.is_none() => player,
+            _ => return,
+        };
 
-        // 碰撞到了边界？
-        if self.board_wall.is_collited_by_block(&self.snake.head) == Collited::WithWall {
-            return Collited::WithWall;
+        match (self.game_status.clone(), action) {
+            (GameStatus::GAMING, PadAction::Steer(direction)) if player == 0 => {
+                self.steer(direction)
+            }
+            (GameStatus::GAMING, PadAction::Steer(_)) => {}
+            (GameStatus::GAMING, PadAction::Start) | (GameStatus::GAMING, PadAction::Back) => {
+                self.pause()
+            }
+            (GameStatus::TIMEOUT, PadAction::Start) => self.resume(),
+            (GameStatus::RESUMING, PadAction::Start) => self.cancel_resume(),
+            (GameStatus::GAMEOVER, PadAction::Start) => self.game_status = GameStatus::RESTART,
+            (_, action) if self.is_in_menu() => {
+                let key = match action {
+                    PadAction::Steer(Direction::Up) => Key::Up,
+                    PadAction::Steer(Direction::Down) => Key::Down,
+                    PadAction::Steer(Direction::Left) => Key::Left,
+                    PadAction::Steer(Direction::Right) => Key::Right,
+                    PadAction::Confirm => Key::Return,
+                    PadAction::Back | PadAction::Start => Key::Escape,
+                };
+                self.menu_key_press(key);
+            }
+            _ => {}
         }
+    }
+
+    // 松开按键
+    fn release(&mut self, button: &Button) {
+        if let &Button::Mouse(_) = button {
+            self.editor.painting = None;
 
-        // 碰撞到了墙壁？
-        for wall in self.walls.iter() {
-            if wall.is_collited_by_block(&self.snake.head) == Collited::WithWall {
-                return Collited::WithWall;
+            // 没有滑动的点击让蛇转向点击的位置
+            if self.drag_start.take().is_some()
+                && !self.swiped
+                && self.game_status == GameStatus::GAMING
+            {
+                self.steer_towards(self.cursor);
             }
         }
+    }
 
-        // 碰撞到了食物？
-        if self.fruit.is_collited_by_block(&self.snake.head) == Collited::WithFruit {
-            return Collited::WithFruit;
+    fn key_press(&mut self, key: Key) {
+        // 静音键和全屏键在任何界面都有效
+        if self.binding.is_none() {
+            if key == self.settings.keys.mute {
+                self.change_setting(Setting::Mute, 1);
+                return;
+            }
+            if key == Key::F11 {
+                self.change_setting(Setting::Fullscreen, 1);
+                return;
+            }
         }
 
-        Collited::NoCollision
+        match self.game_status {
+            GameStatus::GAMEOVER if key == Key::Space => self.game_status = GameStatus::RESTART,
+            GameStatus::MENU | GameStatus::TIMEOUT | GameStatus::GAMEOVER => {
+                self.menu_key_press(key)
+            }
+            GameStatus::GAMING => self.game_key_press(key),
+            GameStatus::RESUMING if key == self.settings.keys.pause || key == Key::Escape => {
+                self.cancel_resume()
+            }
+            GameStatus::EDITOR => self.editor_key_press(key),
+            _ => {}
+        }
     }
 
-    // 按键判定
-    fn press(&mut self, button: &Button) {
-        match button {
-            &Button::Keyboard(key) => self.key_press(key),
+    // 菜单中的按键
+    fn menu_key_press(&mut self, key: Key) {
+        // 正在修改按键绑定时，下一次按下的键就是新的绑定，Esc取消
+        if let Some(setting) = self.binding.take() {
+            if key != Key::Escape {
+                match self.settings.bind(setting, key) {
+                    true => self.apply_settings(),
+                    false => {
+                        self.menu.message = Some(trf("{} is reserved", &[&format!("{:?}", key)]))
+                    }
+                }
+            }
+            return;
+        }
+
+        match key {
+            // 暂停菜单中按暂停键继续游戏，优先于菜单导航
+            key if key == self.settings.keys.pause && self.game_status == GameStatus::TIMEOUT => {
+                self.resume()
+            }
+            Key::Up | Key::W => {
+                self.menu.select_prev();
+                self.mixer.play(Sound::Menu);
+            }
+            Key::Down | Key::S => {
+                self.menu.select_next();
+                self.mixer.play(Sound::Menu);
+            }
+            Key::Left | Key::A => self.menu_adjust(-1),
+            Key::Right | Key::D => self.menu_adjust(1),
+            Key::Return => {
+                if let Some(action) = self.menu.current() {
+                    self.mixer.play(Sound::Menu);
+                    self.activate(action);
+                }
+            }
+            Key::F5 if self.game_status == GameStatus::TIMEOUT => self.save_game(),
+            Key::Escape | Key::Backspace => self.activate(MenuAction::Back),
             _ => {}
         }
     }
 
-    fn key_press(&mut self, key: Key) {
-        if self.snake.direction_lock == false {
-            self.snake.direction_lock = true;
+    // 鼠标移动时选中光标下的菜单项
+    fn mouse_move(&mut self, pos: [f64; 2]) {
+        self.cursor = pos;
+
+        // 在编辑器中按住鼠标拖动时连续绘制或擦除
+        if let Some(tool) = self.editor.painting {
+            if let Some(cell) = self.layout.cell_at(&self.cursor) {
+                self.editor.apply(tool, cell);
+            }
         }
 
-        match key {
-            Key::Up if self.snake.direction != Direction::Down => {
-                self.snake.direction = Direction::Up;
+        // 游戏中按住鼠标滑动足够的距离时按滑动的方向转向，之后从当前位置继续识别
+        let playing = self.game_status == GameStatus::GAMING;
+        if let (Some(start), true) = (self.drag_start, playing) {
+            let delta = [pos[0] - start[0], pos[1] - start[1]];
+            if delta[0].hypot(delta[1]) >= consts::SWIPE_DISTANCE {
+                if let Some(direction) = direction_of(delta) {
+                    self.steer(direction);
+                }
+                self.drag_start = Some(pos);
+                self.swiped = true;
+            }
+        }
+
+        if self.is_in_menu() {
+            match self.menu.item_at(&self.cursor, &self.window_size) {
+                Some(index) if index != self.menu.selected => {
+                    self.menu.selected = index;
+                    self.mixer.play(Sound::Menu);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // 在菜单中左右调整：设置项调整数值，高分榜切换显示的模式
+    fn menu_adjust(&mut self, step: i32) {
+        if self.menu.page == MenuPage::HighScores {
+            let len = GameMode::ALL.len() as i32;
+            let index = GameMode::ALL
+                .iter()
+                .position(|&mode| mode == self.high_score_view)
+                .unwrap_or(0) as i32;
+            self.high_score_view = GameMode::ALL[((index + step + len) % len) as usize];
+            self.mixer.play(Sound::Menu);
+        } else if let Some(MenuAction::Setting(setting)) = self.menu.current() {
+            self.change_setting(setting, step);
+            self.mixer.play(Sound::Menu);
+        }
+    }
+
+    // 鼠标点击菜单项，游戏中点击屏幕按钮或开始识别点击和滑动
+    fn mouse_click(&mut self) {
+        if self.game_status == GameStatus::GAMING {
+            match self.button_at(&self.cursor) {
+                Some(HudButton::Pause) => self.pause(),
+                Some(HudButton::Restart) => self.game_status = GameStatus::RESTART,
+                None if self.settings.mouse_control => {
+                    self.drag_start = Some(self.cursor);
+                    self.swiped = false;
+                }
+                None => (),
             }
-            Key::Down if self.snake.direction != Direction::Up => {
-                self.snake.direction = Direction::Down;
+            return;
+        }
+        if !self.is_in_menu() {
+            return;
+        }
+
+        if let Some(index) = self.menu.item_at(&self.cursor, &self.window_size) {
+            self.menu.selected = index;
+            if let Some(action) = self.menu.current() {
+                self.mixer.play(Sound::Menu);
+                self.activate(action);
             }
-            Key::Left if self.snake.direction != Direction::Right => {
-                self.snake.direction = Direction::Left;
+        }
+    }
+
+    /// 当前显示的屏幕按钮，只在开启鼠标操作时显示
+    pub fn hud_buttons(&self) -> &'static [HudButton] {
+        match self.settings.mouse_control {
+            true => &HudButton::ALL,
+            false => &[],
+        }
+    }
+
+    /// 窗口中的位置所在的屏幕按钮
+    pub fn button_at(&self, pos: &[f64; 2]) -> Option<HudButton> {
+        let first = self.settings.hud.len();
+
+        self.hud_buttons()
+            .iter()
+            .enumerate()
+            .find(|(i, _)| {
+                let rect = self.layout.hud_item_rect(first + i);
+                pos[0] >= rect[0]
+                    && pos[0] < rect[0] + rect[2]
+                    && pos[1] >= rect[1]
+                    && pos[1] < rect[1] + rect[3]
+            })
+            .map(|(_, &button)| button)
+    }
+
+    // 让蛇转向窗口中的位置，按位置相对蛇头的方向决定
+    fn steer_towards(&mut self, pos: [f64; 2]) {
+        let head = &self.game.snake.head;
+        let head = self.layout.cell_rect(head.pos_x, head.pos_y);
+        let delta = [
+            pos[0] - (head[0] + head[2] * 0.5),
+            pos[1] - (head[1] + head[3] * 0.5),
+        ];
+
+        if let Some(direction) = direction_of(delta) {
+            self.steer(direction);
+        }
+    }
+
+    /// 当前是否显示菜单
+    pub fn is_in_menu(&self) -> bool {
+        matches!(
+            self.game_status,
+            GameStatus::MENU | GameStatus::TIMEOUT | GameStatus::GAMEOVER
+        )
+    }
+
+    /// 打开关卡编辑器，指定了关卡文件时读取这个文件
+    pub fn open_editor(&mut self, path: Option<PathBuf>) {
+        if let Some(path) = path {
+            self.editor = Editor::new(self.settings.board_size, path);
+            self.editor.load();
+        }
+
+        self.circus = self.editor.circus();
+        self.game_status = GameStatus::EDITOR;
+    }
+
+    // 在编辑器中按下鼠标，画笔类的工具可以按住拖动
+    fn editor_mouse_press(&mut self, tool: Tool) {
+        if let Some(cell) = self.layout.cell_at(&self.cursor) {
+            self.editor.apply(tool, cell);
+        }
+        if tool.is_brush() {
+            self.editor.painting = Some(tool);
+        }
+    }
+
+    // 编辑器中的按键
+    fn editor_key_press(&mut self, key: Key) {
+        match key {
+            Key::D1 | Key::D2 | Key::D3 | Key::D4 | Key::D5 => {
+                let keys = [Key::D1, Key::D2, Key::D3, Key::D4, Key::D5];
+                if let Some(index) = keys.iter().position(|&other| other == key) {
+                    self.editor.tool = Tool::ALL[index];
+                    self.editor.portal_start = None;
+                }
             }
-            Key::Right if self.snake.direction != Direction::Left => {
-                self.snake.direction = Direction::Right;
+            Key::R => self.editor.rotate(),
+            Key::C => self.editor.clear(),
+            Key::F2 => self.editor.save(),
+            Key::F3 => {
+                self.editor.load();
+                self.circus = self.editor.circus();
             }
-            Key::Space => {
-                self.game_status = match self.game_status {
-                    GameStatus::GAMING => GameStatus::TIMEOUT,
-                    _ => GameStatus::RESTART,
+            // 立即试玩，关卡无法试玩时显示原因
+            Key::T | Key::Return => match self.editor.validate() {
+                Ok(()) => {
+                    self.level = Level::Custom;
+                    self.new_game();
                 }
+                Err(e) => self.editor.message = Some(e),
+            },
+            Key::Escape => self.open_main_menu(),
+            _ => (),
+        }
+    }
+
+    // 返回标题界面
+    fn open_main_menu(&mut self) {
+        self.menu.reset(MenuPage::Title);
+        self.menu.can_continue = SavedGame::exists();
+        self.game_status = GameStatus::MENU;
+    }
+
+    // 执行菜单项对应的动作
+    fn activate(&mut self, action: MenuAction) {
+        match action {
+            MenuAction::Continue => self.continue_game(),
+            MenuAction::NewGame => self.menu.open(MenuPage::ModeSelect),
+            MenuAction::Mode(mode) => {
+                self.mode = mode;
+                self.menu.open(MenuPage::LevelSelect);
             }
-            Key::Return => {
-                self.game_status = match self.game_status {
-                    GameStatus::TIMEOUT => GameStatus::GAMING,
-                    _ => self.game_status.clone(),
+            MenuAction::Level(level) => {
+                self.level = level;
+                self.new_game();
+            }
+            MenuAction::Options => self.menu.open(MenuPage::Options),
+            MenuAction::Open(page) => self.menu.open(page),
+            MenuAction::Setting(setting) if setting.is_key() => self.binding = Some(setting),
+            MenuAction::Setting(setting) => self.change_setting(setting, 1),
+            // 高分榜先显示当前模式的分数，切换显示的模式不影响要玩的模式
+            MenuAction::HighScores => {
+                self.high_score_view = self.mode;
+                self.menu.open(MenuPage::HighScores);
+            }
+            MenuAction::Resume => self.resume(),
+            MenuAction::Restart => self.game_status = GameStatus::RESTART,
+            MenuAction::SaveReplay => {
+                self.menu.message = Some(match self.replay.save() {
+                    Ok(path) => trf("Replay saved to {}", &[&path.display()]),
+                    Err(e) => trf("Failed to save replay: {}", &[&e]),
+                });
+            }
+            MenuAction::Editor => self.open_editor(None),
+            // 禅模式不会死亡，从暂停菜单离开时结算本局
+            MenuAction::MainMenu
+                if self.game_status == GameStatus::TIMEOUT && self.game.mode == GameMode::Zen =>
+            {
+                self.game.finish();
+                self.dispatch();
+                self.end_game()
+            }
+            MenuAction::MainMenu => self.open_main_menu(),
+            MenuAction::Back => {
+                if self.game_status == GameStatus::TIMEOUT && self.menu.page == MenuPage::Pause {
+                    self.resume();
+                } else if self.menu.page == MenuPage::GameOver {
+                    self.open_main_menu();
+                } else if !self.menu.back() && self.menu.page == MenuPage::Title {
+                    self.quit();
                 }
             }
+            MenuAction::Quit => self.quit(),
+        }
+    }
+
+    // 游戏中的按键
+    fn game_key_press(&mut self, key: Key) {
+        let keys = self.settings.keys.clone();
+
+        match key {
+            k if k == keys.up => self.steer(Direction::Up),
+            k if k == keys.down => self.steer(Direction::Down),
+            k if k == keys.left => self.steer(Direction::Left),
+            k if k == keys.right => self.steer(Direction::Right),
+            k if k == keys.pause || k == Key::Escape => self.pause(),
             _ => {}
         }
     }
 
-    /// 运行程序
-    pub fn run(&mut self) {
-        // piston标准结构，与渲染有关
-        let opengl = OpenGL::V3_2;
+    // 让蛇转向，转向事件立即发出，声音不会等到下一步才播放
+    fn steer(&mut self, to: Direction) {
+        self.game.turn(to);
+        self.dispatch();
+    }
 
-        // 创建一个Glutin窗口
-        let mut window: Window = WindowSettings::new("snakes", [640, 480])
-            .graphics_api(opengl)
-            .exit_on_esc(true)
-            .build()
-            .unwrap_or_else(|e| panic!("Failed to build PistonWindow: {}", e));
+    /// 运行程序
+    pub fn run(&mut self) -> Result<(), Error> {
+        // 创建一个Glutin窗口，显卡不支持时使用较低的OpenGL版本
+        let (mut window, opengl) = build_window(self.settings.fullscreen)?;
 
         // piston标准结构，与渲染有关
         let mut gl = GlGraphics::new(opengl);
 
+        // 纹理需要在OpenGL上下文创建之后读取
+        self.load_tileset();
+
         // 创建一个新的事件并设置更新频率
-        let ref mut events = Events::new(EventSettings::new());
+        let events = &mut Events::new(EventSettings::new());
         events.set_ups(60);
 
-        let circus = self.circus;
+        // 窗口当前是否全屏
+        let mut fullscreen = self.settings.fullscreen;
 
         // piston引擎的主要循环，是以迭代器的形式实现的
         while let Some(e) = events.next(&mut window) {
             if let Some(args) = e.render_args() {
+                // 每一帧按窗口尺寸重新计算布局，移动空间的尺寸也会随设置改变
+                // 编辑器在HUD区域显示一行提示
+                let hud_items = match self.game_status {
+                    GameStatus::EDITOR => 1,
+                    _ => self.settings.hud.len() + self.hud_buttons().len(),
+                };
+                let layout = Layout::from_args(&self.circus, &args, hud_items);
                 gl.draw(args.viewport(), |c, gl| {
-                    self.render( &circus, &args, gl, c);
+                    self.render(&layout, &args, gl, c);
                 });
             }
             if let Some(args) = e.update_args() {
+                // 窗口后端不报告手柄事件，每次更新前读取一次
+                self.poll_gamepads();
                 self.update(&args);
             }
+            if let Some(pos) = e.mouse_cursor_args() {
+                self.mouse_move(pos);
+            }
             if let Some(button) = e.press_args() {
                 self.press(&button);
             }
+            if let Some(button) = e.release_args() {
+                self.release(&button);
+            }
+            if let Some(focused) = e.focus_args() {
+                self.focus(focused);
+            }
+
+            // 全屏设置改变时切换窗口
+            if self.settings.fullscreen != fullscreen {
+                fullscreen = self.settings.fullscreen;
+                let glutin_window = window.ctx.window();
+                let monitor = match fullscreen {
+                    true => Some(glutin_window.get_current_monitor()),
+                    false => None,
+                };
+                glutin_window.set_fullscreen(monitor);
+            }
+
+            // 选择了退出
+            if self.game_status == GameStatus::QUIT {
+                break;
+            }
+        }
+
+        // 直接关闭窗口时也保存正在进行的一局
+        if self.is_playing() {
+            self.save_game();
         }
+
+        Ok(())
     }
 }
 
-impl Clone for App<'_> {
-    fn clone(&self) -> Self {
-        // 为了能够渲染文字，需要读取字体缓存
-        let texture_settings = TextureSettings::new().filter(Filter::Nearest);
-        let glyph_cache = GlyphCache::new("assets/Roboto-Regular.ttf", (), texture_settings)
-            .expect("Error unwrapping fonts");
-
-        App {
-            game_status: self.game_status.clone(),
-            circus: self.circus,
-            update_time: self.update_time,
-            score: self.score,
-            board_wall: self.board_wall.clone(),
-            walls: self.walls.clone(),
-            fruit: self.fruit.clone(),
-            snake: self.snake.clone(),
-            glyph_cache
+// 按界面语言读取字体并建立字体缓存，读取的字体无法使用时退回到嵌入的字体
+fn load_glyph_cache() -> Result<GlyphCache<'static>, Error> {
+    let font = match locale::current().needs_cjk_font() {
+        true => load_cjk_font(),
+        false => load_font(),
+    }?;
+
+    match glyph_cache(font) {
+        Ok(glyph_cache) => Ok(glyph_cache),
+        Err(e) => {
+            eprintln!("{}, using the built-in font", e);
+            let font = assets::embedded(assets::FONT).ok_or_else(|| {
+                Error::Asset(assets::FONT.to_string(), "not embedded".to_string())
+            })?;
+            glyph_cache(font)
         }
     }
 }
+
+// 按关卡摆放新的一局，关卡中随机的部分和之后的食物、道具都由`seed`决定
+// 自定义关卡按编辑器中的布局摆放，没有放置食物时随机放置
+fn build_game(
+    mode: GameMode,
+    level: Level,
+    settings: &Settings,
+    editor: &Editor,
+    seed: u64,
+) -> Game {
+    let mut rng = Pcg64Mcg::seed_from_u64(seed);
+    let (circus, mut setup) = match level {
+        Level::Custom => {
+            let (circus, map) = (editor.circus(), &editor.map);
+            let setup = Setup {
+                walls: map.walls(&circus),
+                dynamic_walls: Vec::new(),
+                portals: map.portals(&circus),
+                snake: map.snake(&circus),
+                fruit: map.fruit(&circus),
+            };
+            (circus, setup)
+        }
+        level => {
+            let circus = settings.board_size;
+            let walls = level.walls(&circus, settings, &mut rng);
+            let setup = Setup {
+                portals: level.portals(&circus, &walls, &mut rng),
+                dynamic_walls: level.dynamic_walls(&circus),
+                walls,
+                snake: Snake::new(circus[0], circus[1]),
+                fruit: None,
+            };
+            (circus, setup)
+        }
+    };
+    setup.snake.velocity = settings.speed;
+
+    Game::start(mode, circus, seed, rng, setup)
+}
+
+// 读取能显示汉字的字体，只读取一次，找不到时退回到默认字体
+fn load_cjk_font() -> Result<&'static [u8], Error> {
+    static CJK_FONT: OnceLock<Option<&'static [u8]>> = OnceLock::new();
+
+    let font = *CJK_FONT.get_or_init(|| {
+        assets::cjk_fonts()
+            .into_iter()
+            .filter_map(|path| fs::read(path).ok())
+            .find(|font| glyph_cache(font).is_ok())
+            .map(|font| &*Box::leak(font.into_boxed_slice()))
+    });
+
+    match font {
+        Some(font) => Ok(font),
+        None => {
+            eprintln!(
+                "No CJK font found, put one named {} in the assets directory",
+                assets::CJK_FONT
+            );
+            load_font()
+        }
+    }
+}
+
+// 读取字体，资源目录中的字体优先于嵌入的字体，只在第一次成功时读取
+fn load_font() -> Result<&'static [u8], Error> {
+    static FONT: OnceLock<&'static [u8]> = OnceLock::new();
+
+    if let Some(font) = FONT.get() {
+        return Ok(font);
+    }
+
+    let font = assets::read(assets::FONT)
+        .map_err(|e| Error::Asset(assets::FONT.to_string(), e.to_string()))?;
+    let font = match font {
+        Cow::Borrowed(bytes) => bytes,
+        // 字体在整个程序运行期间都会用到，读取的文件不需要释放
+        Cow::Owned(bytes) => &*Box::leak(bytes.into_boxed_slice()),
+    };
+
+    Ok(FONT.get_or_init(|| font))
+}
+
+// 读取图块集图片，`path`不是文件时按资源名在查找目录和嵌入的资源中找
+fn read_tileset(path: &str) -> Result<Cow<'static, [u8]>, String> {
+    let read = match Path::new(path).is_file() {
+        true => fs::read(path).map(Cow::Owned),
+        false => assets::read(path),
+    };
+
+    read.map_err(|e| e.to_string())
+}
+
+// 用字体数据建立字体缓存
+fn glyph_cache(font: &[u8]) -> Result<GlyphCache<'_>, Error> {
+    let texture_settings = TextureSettings::new().filter(Filter::Nearest);
+
+    GlyphCache::from_bytes(font, (), texture_settings)
+        .map_err(|e| Error::Asset(assets::FONT.to_string(), format!("invalid font: {:?}", e)))
+}
+
+// 按顺序尝试OpenGL版本创建窗口，返回窗口和成功的版本
+fn build_window(fullscreen: bool) -> Result<(Window, OpenGL), Error> {
+    let mut reason = String::new();
+
+    for &opengl in OPENGL_VERSIONS.iter() {
+        let window = WindowSettings::new("snakes", [640, 480])
+            .graphics_api(opengl)
+            .fullscreen(fullscreen)
+            .exit_on_esc(false)
+            .build();
+
+        match window {
+            Ok(window) => return Ok((window, opengl)),
+            Err(e) => {
+                eprintln!("Failed to create window with OpenGL {:?}: {}", opengl, e);
+                reason = e.to_string();
+            }
+        }
+    }
+
+    Err(Error::Window(reason))
+}
+
+// 按位移较大的分量决定方向，窗口坐标的y轴向下，没有位移时返回None
+fn direction_of(delta: [f64; 2]) -> Option<Direction> {
+    let [x, y] = delta;
+    if x == 0.0 && y == 0.0 {
+        return None;
+    }
+
+    match x.abs() > y.abs() {
+        true if x > 0.0 => Some(Direction::Right),
+        true => Some(Direction::Left),
+        false if y > 0.0 => Some(Direction::Down),
+        false => Some(Direction::Up),
+    }
+}
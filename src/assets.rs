@@ -0,0 +1,145 @@
+use std::borrow::Cow;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 指定资源目录的环境变量
+pub const ASSETS_ENV: &str = "SNAKE_ASSETS";
+
+/// 界面使用的字体
+pub const FONT: &str = "Roboto-Regular.ttf";
+
+/// 界面语言为中文时使用的字体，放在资源目录中时优先于系统字体
+pub const CJK_FONT: &str = "cjk.ttf";
+
+/// 常见系统中能显示汉字的字体文件，字体集合中只有一种字体时才能使用
+const SYSTEM_CJK_FONTS: &[&str] = &[
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/google-droid/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+];
+
+// 嵌入`assets`目录中的文件，名字就是相对于资源目录的路径
+macro_rules! embed {
+    ($name:literal) => {
+        ($name, include_bytes!(concat!("../assets/", $name)) as &[u8])
+    };
+}
+
+/// 嵌入程序的字体
+const EMBEDDED_FONTS: &[(&str, &[u8])] = &[embed!("Roboto-Regular.ttf")];
+
+/// 嵌入程序的声音
+#[cfg(feature = "audio")]
+const EMBEDDED_SOUNDS: &[(&str, &[u8])] = &[
+    embed!("sounds/eat.wav"),
+    embed!("sounds/turn.wav"),
+    embed!("sounds/die.wav"),
+    embed!("sounds/level_up.wav"),
+    embed!("sounds/menu.wav"),
+    embed!("sounds/pause.wav"),
+    embed!("sounds/power_up.wav"),
+    embed!("sounds/music.wav"),
+];
+
+#[cfg(not(feature = "audio"))]
+const EMBEDDED_SOUNDS: &[(&str, &[u8])] = &[];
+
+/// 嵌入程序的图片
+const EMBEDDED_IMAGES: &[(&str, &[u8])] = &[embed!("tileset.png")];
+
+/// 查找资源文件的目录，按优先级排列：
+/// 环境变量`SNAKE_ASSETS`指定的目录、可执行文件旁边的`assets`、
+/// 数据目录中的`snake/assets`、当前目录中的`assets`
+pub fn search_path() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Some(dir) = env::var_os(ASSETS_ENV) {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join("assets")))
+    {
+        dirs.push(dir);
+    }
+    if let Some(dir) = dirs::data_dir() {
+        dirs.push(dir.join("snake").join("assets"));
+    }
+    dirs.push(PathBuf::from("assets"));
+
+    dirs
+}
+
+/// 在查找目录中找到资源文件，`name`是相对于资源目录的路径
+/// 以`assets/`开头的路径也可以找到，兼容旧的设置文件
+pub fn find<P: AsRef<Path>>(name: P) -> Option<PathBuf> {
+    let name = name.as_ref();
+    let name = name.strip_prefix("assets").unwrap_or(name);
+
+    search_path()
+        .into_iter()
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// 玩家指定的资源文件，文件存在时直接使用，否则在查找目录中找
+pub fn locate<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
+    let path = path.as_ref();
+
+    match path.is_file() {
+        true => Some(path.to_path_buf()),
+        false => find(path),
+    }
+}
+
+/// 能显示汉字的字体文件，资源目录中的`cjk.ttf`排在系统字体前面
+pub fn cjk_fonts() -> Vec<PathBuf> {
+    find(CJK_FONT)
+        .into_iter()
+        .chain(
+            SYSTEM_CJK_FONTS
+                .iter()
+                .map(PathBuf::from)
+                .filter(|path| path.is_file()),
+        )
+        .collect()
+}
+
+/// 嵌入程序的资源
+pub fn embedded(name: &str) -> Option<&'static [u8]> {
+    EMBEDDED_FONTS
+        .iter()
+        .chain(EMBEDDED_SOUNDS.iter())
+        .chain(EMBEDDED_IMAGES.iter())
+        .find(|(embedded, _)| *embedded == name)
+        .map(|(_, bytes)| *bytes)
+}
+
+/// 读取资源，查找目录中的文件优先于嵌入的资源，所以可以覆盖内置的字体、声音和图块集
+/// 查找目录中的文件无法读取时报告错误并退回到嵌入的资源
+pub fn read(name: &str) -> io::Result<Cow<'static, [u8]>> {
+    if let Some(path) = find(name) {
+        match fs::read(&path) {
+            Ok(bytes) => return Ok(Cow::Owned(bytes)),
+            Err(e) => eprintln!("Failed to read {}: {}", path.display(), e),
+        }
+    }
+
+    embedded(name).map(Cow::Borrowed).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "asset {} not found, set {} to the assets directory",
+                name, ASSETS_ENV
+            ),
+        )
+    })
+}
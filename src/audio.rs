@@ -0,0 +1,372 @@
+use crate::{
+    event::{GameEvent, Subscriber},
+    settings::Settings,
+};
+
+/// 游戏中会播放的音效
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Sound {
+    Eat,     // 吃到食物
+    Turn,    // 转向
+    Die,     // 死亡
+    LevelUp, // 升级
+    Pause,   // 暂停
+    Menu,    // 菜单导航
+    PowerUp, // 拾取道具
+}
+
+impl Sound {
+    /// 所有音效
+    pub const ALL: [Sound; 7] = [
+        Sound::Eat,
+        Sound::Turn,
+        Sound::Die,
+        Sound::LevelUp,
+        Sound::Pause,
+        Sound::Menu,
+        Sound::PowerUp,
+    ];
+
+    /// 音效文件在`assets/sounds`中的文件名
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Sound::Eat => "eat.wav",
+            Sound::Turn => "turn.wav",
+            Sound::Die => "die.wav",
+            Sound::LevelUp => "level_up.wav",
+            Sound::Pause => "pause.wav",
+            Sound::Menu => "menu.wav",
+            Sound::PowerUp => "power_up.wav",
+        }
+    }
+}
+
+/// 背景音乐的文件名
+pub const MUSIC_FILE: &str = "music.wav";
+
+/// 声音输出的后端
+pub trait Backend {
+    /// 以`volume`的音量播放一次音效
+    fn play(&mut self, sound: Sound, volume: f32);
+
+    /// 播放或暂停循环的背景音乐
+    fn music(&mut self, playing: bool);
+
+    /// 修改背景音乐的音量
+    fn music_volume(&mut self, volume: f32);
+}
+
+/// 什么都不播放的后端，用于没有声音设备的环境
+pub struct NullBackend;
+
+impl Backend for NullBackend {
+    fn play(&mut self, _sound: Sound, _volume: f32) {}
+
+    fn music(&mut self, _playing: bool) {}
+
+    fn music_volume(&mut self, _volume: f32) {}
+}
+
+/// 混音器，按设置中的音量和静音状态把声音交给后端播放
+pub struct Mixer {
+    backend: Box<dyn Backend>,
+    effects_volume: f32,
+    music_volume: f32,
+    muted: bool,
+}
+
+impl Mixer {
+    /// 创建混音器，没有可用的声音设备时使用空后端
+    pub fn new(settings: &Settings) -> Self {
+        #[cfg(feature = "audio")]
+        {
+            if let Some(backend) = rodio_backend::RodioBackend::new() {
+                return Mixer::with_backend(Box::new(backend), settings);
+            }
+        }
+
+        Mixer::null(settings)
+    }
+
+    /// 创建使用空后端的混音器
+    pub fn null(settings: &Settings) -> Self {
+        Mixer::with_backend(Box::new(NullBackend), settings)
+    }
+
+    /// 使用指定的后端创建混音器
+    pub fn with_backend(backend: Box<dyn Backend>, settings: &Settings) -> Self {
+        let mut mixer = Mixer {
+            backend,
+            effects_volume: 0.0,
+            music_volume: 0.0,
+            muted: false,
+        };
+        mixer.apply(settings);
+
+        mixer
+    }
+
+    /// 应用设置中的音量和静音状态
+    pub fn apply(&mut self, settings: &Settings) {
+        self.effects_volume = settings.volume;
+        self.music_volume = settings.music_volume;
+        self.muted = settings.muted;
+
+        let volume = self.volume(self.music_volume);
+        self.backend.music_volume(volume);
+    }
+
+    /// 播放一次音效
+    pub fn play(&mut self, sound: Sound) {
+        let volume = self.volume(self.effects_volume);
+        if volume > 0.0 {
+            self.backend.play(sound, volume);
+        }
+    }
+
+    /// 播放或暂停背景音乐
+    pub fn music(&mut self, playing: bool) {
+        self.backend.music(playing);
+    }
+
+    // 静音时音量为0
+    fn volume(&self, volume: f32) -> f32 {
+        match self.muted {
+            true => 0.0,
+            false => volume,
+        }
+    }
+}
+
+impl Subscriber for Mixer {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Started { .. } | GameEvent::Resumed => self.music(true),
+            GameEvent::Turned { .. } => self.play(Sound::Turn),
+            GameEvent::AteFruit { .. } => self.play(Sound::Eat),
+            GameEvent::PowerUpCollected { .. } => self.play(Sound::PowerUp),
+            GameEvent::LevelCompleted { .. } => self.play(Sound::LevelUp),
+            GameEvent::Collided { .. } => self.play(Sound::Die),
+            GameEvent::Paused => {
+                self.play(Sound::Pause);
+                self.music(false);
+            }
+            GameEvent::Ended { .. } => self.music(false),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+mod rodio_backend {
+    use super::{Backend, Sound, MUSIC_FILE};
+    use crate::assets;
+    use rodio::{Decoder, Device, Sink, Source};
+    use std::collections::HashMap;
+    use std::io::{self, Cursor};
+
+    // 读取资源目录中`sounds`下的声音
+    fn read_sound(file_name: &str) -> io::Result<Vec<u8>> {
+        assets::read(&format!("sounds/{}", file_name)).map(|bytes| bytes.into_owned())
+    }
+
+    /// 使用rodio播放声音的后端
+    pub struct RodioBackend {
+        device: Device,
+        sounds: HashMap<&'static str, Vec<u8>>,
+        music: Option<Sink>,
+    }
+
+    impl RodioBackend {
+        /// 打开默认的声音设备并读取所有音效，没有声音设备时返回None
+        pub fn new() -> Option<Self> {
+            let device = rodio::default_output_device()?;
+
+            let mut sounds = HashMap::new();
+            for sound in Sound::ALL.iter() {
+                match read_sound(sound.file_name()) {
+                    Ok(bytes) => {
+                        sounds.insert(sound.file_name(), bytes);
+                    }
+                    Err(e) => eprintln!("Failed to load sound {}: {}", sound.file_name(), e),
+                }
+            }
+
+            let music = read_sound(MUSIC_FILE)
+                .ok()
+                .and_then(|bytes| Decoder::new(Cursor::new(bytes)).ok())
+                .map(|source| {
+                    let sink = Sink::new(&device);
+                    sink.pause();
+                    sink.append(source.repeat_infinite());
+                    sink
+                });
+
+            Some(RodioBackend {
+                device,
+                sounds,
+                music,
+            })
+        }
+    }
+
+    impl Backend for RodioBackend {
+        fn play(&mut self, sound: Sound, volume: f32) {
+            let bytes = match self.sounds.get(sound.file_name()) {
+                Some(bytes) => bytes.clone(),
+                None => return,
+            };
+
+            if let Ok(source) = Decoder::new(Cursor::new(bytes)) {
+                let sink = Sink::new(&self.device);
+                sink.set_volume(volume);
+                sink.append(source);
+                sink.detach();
+            }
+        }
+
+        fn music(&mut self, playing: bool) {
+            if let Some(sink) = &self.music {
+                match playing {
+                    true => sink.play(),
+                    false => sink.pause(),
+                }
+            }
+        }
+
+        fn music_volume(&mut self, volume: f32) {
+            if let Some(sink) = &self.music {
+                sink.set_volume(volume);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{event::Obstacle, powerup::PowerUpKind, Direction};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // 记录收到的所有调用的后端
+    #[derive(Default, Clone)]
+    struct Recorder {
+        played: Rc<RefCell<Vec<(Sound, f32)>>>,
+        music: Rc<RefCell<Vec<bool>>>,
+        music_volume: Rc<RefCell<f32>>,
+    }
+
+    impl Backend for Recorder {
+        fn play(&mut self, sound: Sound, volume: f32) {
+            self.played.borrow_mut().push((sound, volume));
+        }
+
+        fn music(&mut self, playing: bool) {
+            self.music.borrow_mut().push(playing);
+        }
+
+        fn music_volume(&mut self, volume: f32) {
+            *self.music_volume.borrow_mut() = volume;
+        }
+    }
+
+    fn recording(settings: &Settings) -> (Mixer, Recorder) {
+        let recorder = Recorder::default();
+        let mixer = Mixer::with_backend(Box::new(recorder.clone()), settings);
+        (mixer, recorder)
+    }
+
+    #[test]
+    fn null_backend_accepts_everything() {
+        let mut mixer = Mixer::null(&Settings::default());
+        mixer.play(Sound::Eat);
+        mixer.music(true);
+        mixer.notify(&GameEvent::Paused);
+        mixer.apply(&Settings::default());
+    }
+
+    #[test]
+    fn volume_and_mute_follow_the_settings() {
+        let mut settings = Settings {
+            volume: 0.4,
+            music_volume: 0.6,
+            ..Settings::default()
+        };
+        let (mut mixer, recorder) = recording(&settings);
+
+        mixer.play(Sound::Eat);
+        assert_eq!(*recorder.played.borrow(), vec![(Sound::Eat, 0.4)]);
+        assert_eq!(*recorder.music_volume.borrow(), 0.6);
+
+        settings.muted = true;
+        mixer.apply(&settings);
+        mixer.play(Sound::Eat);
+        assert_eq!(recorder.played.borrow().len(), 1);
+        assert_eq!(*recorder.music_volume.borrow(), 0.0);
+
+        settings.muted = false;
+        settings.volume = 0.0;
+        mixer.apply(&settings);
+        mixer.play(Sound::Turn);
+        assert_eq!(recorder.played.borrow().len(), 1);
+    }
+
+    #[test]
+    fn events_map_to_sounds() {
+        let (mut mixer, recorder) = recording(&Settings::default());
+        let events = [
+            (GameEvent::AteFruit { cell: (1, 1) }, Sound::Eat),
+            (
+                GameEvent::Turned {
+                    from: Direction::Left,
+                    to: Direction::Up,
+                },
+                Sound::Turn,
+            ),
+            (
+                GameEvent::PowerUpCollected {
+                    kind: PowerUpKind::Ghost,
+                    cell: (1, 1),
+                },
+                Sound::PowerUp,
+            ),
+            (GameEvent::LevelCompleted { level: 2 }, Sound::LevelUp),
+            (
+                GameEvent::Collided {
+                    obstacle: Obstacle::Wall,
+                    cell: (0, 0),
+                },
+                Sound::Die,
+            ),
+            (GameEvent::Paused, Sound::Pause),
+        ];
+
+        for (event, sound) in events.iter() {
+            recorder.played.borrow_mut().clear();
+            mixer.notify(event);
+            let played: Vec<Sound> = recorder.played.borrow().iter().map(|p| p.0).collect();
+            assert_eq!(played, vec![*sound], "{:?}", event);
+        }
+
+        recorder.played.borrow_mut().clear();
+        mixer.notify(&GameEvent::Moved { head: (1, 1) });
+        mixer.notify(&GameEvent::FruitSpawned { cell: (1, 1) });
+        assert!(recorder.played.borrow().is_empty());
+    }
+
+    #[test]
+    fn music_follows_the_game() {
+        let (mut mixer, recorder) = recording(&Settings::default());
+
+        mixer.notify(&GameEvent::Started { head: (5, 5) });
+        mixer.notify(&GameEvent::Paused);
+        mixer.notify(&GameEvent::Resumed);
+        mixer.notify(&GameEvent::Ended {
+            score: 3,
+            elapsed: 10.0,
+        });
+
+        assert_eq!(*recorder.music.borrow(), vec![true, false, true, false]);
+    }
+}
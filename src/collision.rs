@@ -1,10 +1,14 @@
-use crate::{Block, Fruit, Snake, Wall};
+use crate::{powerup::PowerUp, Block, Fruit, Snake, Wall};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Collited {
     WithFruit,
     WithSnake,
     WithWall,
+    WithPowerUp,
     NoCollision,
 }
 
@@ -117,3 +121,13 @@ impl Collision for Snake {
         }
     }
 }
+
+impl Collision for PowerUp {
+    fn is_colliting<T: Collision>(&self, object: &T) -> Collited {
+        object.is_collited_by_block(&self.block)
+    }
+
+    fn is_collited_by_block(&self, block: &Block) -> Collited {
+        self.block.is_collited_by_block(block)
+    }
+}
@@ -16,8 +16,81 @@ pub const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
 /// 粉红色
 #[warn(dead_code)]
 pub const PINK: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
-///
+/// 半透明淡蓝色
 #[warn(dead_code)]
 pub const ANGEL: [f32; 4] = [0.5, 0.5, 1.0, 0.5];
 /// 绿色
 pub const GREEN: [f32; 4] = [0.0, 0.5, 0.0, 1.0];
+
+/// 高分榜保留的记录条数
+pub const MAX_HIGH_SCORES: usize = 10;
+
+/// 菜单项的宽度
+pub const MENU_ITEM_WIDTH: f64 = 260.0;
+/// 菜单项的高度
+pub const MENU_ITEM_HEIGHT: f64 = 26.0;
+/// 菜单项之间的间距
+pub const MENU_ITEM_SPACING: f64 = 6.0;
+/// 菜单下方留给提示消息的高度
+pub const MENU_BOTTOM_MARGIN: f64 = 36.0;
+
+/// 可选的移动空间尺寸
+pub const BOARD_SIZES: [[u32; 2]; 4] = [[40, 30], [60, 45], [80, 60], [100, 75]];
+/// 蛇的最低初始速度
+pub const MIN_SPEED: f64 = 2.0;
+/// 蛇的最高初始速度
+pub const MAX_SPEED: f64 = 20.0;
+
+/// 开启图块绘制时默认使用的图块集
+pub const DEFAULT_TILESET: &str = "tileset.png";
+
+/// 每吃到多少个食物升一级
+pub const FRUITS_PER_LEVEL: u32 = 10;
+
+/// HUD每行的高度
+pub const HUD_LINE_HEIGHT: f64 = 20.0;
+/// HUD每项的宽度
+pub const HUD_ITEM_WIDTH: f64 = 120.0;
+/// HUD区域的内边距
+pub const HUD_PADDING: f64 = 8.0;
+
+/// 按住鼠标拖动超过这个距离才算滑动手势
+pub const SWIPE_DISTANCE: f64 = 30.0;
+
+/// 设置中可以分配的手柄数量
+pub const MAX_CONTROLLERS: usize = 4;
+/// 可以分配手柄的玩家数量
+pub const MAX_PLAYERS: usize = 2;
+/// 摇杆死区的最小值
+pub const MIN_DEADZONE: f64 = 0.1;
+/// 摇杆死区的最大值
+pub const MAX_DEADZONE: f64 = 0.9;
+
+/// 场上没有道具时，每一步出现道具的概率
+pub const POWER_UP_CHANCE: f64 = 0.01;
+/// 道具在场上停留的步数
+pub const POWER_UP_LIFETIME: u32 = 80;
+/// 减速道具生效时的速度倍率
+pub const SLOW_MOTION_FACTOR: f64 = 0.5;
+
+/// 吃到食物、拾取道具和撞击时格子闪光的秒数
+pub const FLASH_SECONDS: f64 = 0.4;
+
+/// 从暂停继续游戏前倒计时的秒数
+pub const RESUME_COUNTDOWN: f64 = 3.0;
+
+/// 限时模式的时长，单位为秒
+pub const TIME_ATTACK_SECONDS: f64 = 120.0;
+/// 生存模式中每隔多少秒增加一面墙壁
+pub const SURVIVAL_WALL_SECONDS: f64 = 8.0;
+/// 目标模式需要达到的长度
+pub const TARGET_LENGTH: usize = 30;
+
+/// 终端界面移动空间的最大尺寸
+pub const TUI_MAX_SIZE: [u32; 2] = [80, 60];
+/// 终端界面移动空间的最小尺寸，终端更小时无法运行
+pub const TUI_MIN_SIZE: [u32; 2] = [20, 10];
+/// 终端界面两次更新之间最多等待的毫秒数
+pub const TUI_FRAME_MILLIS: u64 = 15;
+/// 无界面运行时移动空间的尺寸
+pub const HEADLESS_SIZE: [u32; 2] = [80, 60];
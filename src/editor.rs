@@ -0,0 +1,338 @@
+use crate::{
+    consts,
+    locale::{tr, trf},
+    map::LevelMap,
+    Direction, Snake,
+};
+use std::path::PathBuf;
+
+/// 编辑器中的工具
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Tool {
+    Wall,   // 绘制墙壁
+    Erase,  // 擦除格子上的所有东西
+    Spawn,  // 放置蛇的出生位置，再次点击旋转方向
+    Portal, // 依次点击两个格子放置一对传送门
+    Fruit,  // 放置第一个食物
+}
+
+impl Tool {
+    /// 所有工具，按数字键1到5选择
+    pub const ALL: [Tool; 5] = [
+        Tool::Wall,
+        Tool::Erase,
+        Tool::Spawn,
+        Tool::Portal,
+        Tool::Fruit,
+    ];
+
+    /// 工具名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::Wall => "Wall",
+            Tool::Erase => "Erase",
+            Tool::Spawn => "Spawn",
+            Tool::Portal => "Portal",
+            Tool::Fruit => "Fruit",
+        }
+    }
+
+    /// 按住鼠标拖动时是否连续使用
+    pub fn is_brush(&self) -> bool {
+        matches!(self, Tool::Wall | Tool::Erase)
+    }
+}
+
+/// 关卡编辑器，在与游戏相同的格子上编辑关卡
+#[derive(Debug, Clone)]
+pub struct Editor {
+    pub map: LevelMap,                  // 正在编辑的关卡
+    pub tool: Tool,                     // 当前的工具
+    pub portal_start: Option<[i32; 2]>, // 已经放置了第一端的传送门
+    pub painting: Option<Tool>,         // 按住鼠标时正在连续使用的工具
+    pub path: PathBuf,                  // 保存和读取的关卡文件
+    pub message: Option<String>,        // 最近一次保存或读取的结果
+}
+
+impl Editor {
+    /// 玩家关卡所在的目录
+    pub fn dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("snake").join("levels"))
+    }
+
+    /// 默认的关卡文件
+    pub fn default_path() -> PathBuf {
+        Editor::dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("custom.toml")
+    }
+
+    /// 在`circus`尺寸的移动空间中编辑空白关卡，蛇在中央出生
+    pub fn new(circus: [u32; 2], path: PathBuf) -> Self {
+        Editor {
+            map: Editor::blank(circus),
+            tool: Tool::Wall,
+            portal_start: None,
+            painting: None,
+            path,
+            message: None,
+        }
+    }
+
+    // 空白关卡
+    fn blank(circus: [u32; 2]) -> LevelMap {
+        LevelMap {
+            name: "Custom".to_string(),
+            board_size: circus,
+            spawn: Some([
+                ((circus[0] as f64) * 0.5) as i32,
+                ((circus[1] as f64) * 0.5) as i32,
+            ]),
+            direction: Some(Direction::Left),
+            ..LevelMap::default()
+        }
+    }
+
+    /// 移动空间的尺寸
+    pub fn circus(&self) -> [u32; 2] {
+        self.map.board_size
+    }
+
+    /// 在格子上使用`tool`，边框和边框之外的格子不能编辑
+    /// 蛇占据的格子上不能放置墙壁、传送门和食物，放置蛇时清空它占据的格子
+    pub fn apply(&mut self, tool: Tool, cell: (i32, i32)) {
+        if !self.is_inside([cell.0, cell.1]) {
+            return;
+        }
+
+        let cell = [cell.0, cell.1];
+        if tool != Tool::Erase && tool != Tool::Spawn && self.snake_cells().contains(&cell) {
+            return;
+        }
+        match tool {
+            Tool::Wall => {
+                if !self.map.walls.contains(&cell) {
+                    self.erase(cell);
+                    self.map.walls.push(cell);
+                }
+            }
+            Tool::Erase => self.erase(cell),
+            Tool::Spawn => match self.map.spawn == Some(cell) {
+                true => self.rotate(),
+                false => {
+                    self.map.spawn = Some(cell);
+                    self.clear_snake();
+                }
+            },
+            Tool::Portal => match self.portal_start.take() {
+                Some(start) if start != cell => {
+                    self.erase(cell);
+                    self.map.portals.push([start, cell]);
+                }
+                Some(_) => (),
+                None => {
+                    self.erase(cell);
+                    self.portal_start = Some(cell);
+                }
+            },
+            Tool::Fruit => {
+                self.erase(cell);
+                self.map.fruit = Some(cell);
+            }
+        }
+    }
+
+    // 格子是否在边框之内
+    fn is_inside(&self, cell: [i32; 2]) -> bool {
+        let [width, height] = self.circus();
+        cell[0] > 0 && cell[1] > 0 && cell[0] < width as i32 && cell[1] < height as i32
+    }
+
+    // 出生时蛇占据的格子
+    fn snake_cells(&self) -> Vec<[i32; 2]> {
+        let snake = self.snake();
+        std::iter::once(&snake.head)
+            .chain(snake.body.iter())
+            .map(|block| [block.pos_x, block.pos_y])
+            .collect()
+    }
+
+    // 擦除蛇占据的格子上的东西，正在放置的传送门也放弃
+    fn clear_snake(&mut self) {
+        for cell in self.snake_cells() {
+            self.erase(cell);
+        }
+        self.portal_start = None;
+    }
+
+    // 擦除格子上的墙壁、传送门和食物，出生位置不会被擦除
+    fn erase(&mut self, cell: [i32; 2]) {
+        self.map.walls.retain(|&wall| wall != cell);
+        self.map.portals.retain(|pair| !pair.contains(&cell));
+        if self.map.fruit == Some(cell) {
+            self.map.fruit = None;
+        }
+        if self.portal_start == Some(cell) {
+            self.portal_start = None;
+        }
+    }
+
+    /// 顺时针旋转蛇出生时的方向，转到的格子上的东西被擦除
+    pub fn rotate(&mut self) {
+        self.map.direction = Some(match self.map.direction {
+            Some(Direction::Up) => Direction::Right,
+            Some(Direction::Right) => Direction::Down,
+            Some(Direction::Down) => Direction::Left,
+            _ => Direction::Up,
+        });
+        self.clear_snake();
+    }
+
+    /// 检查关卡能否试玩，不能时返回原因
+    /// 尺寸不能小于可选的最小尺寸，蛇必须整条在边框之内，蛇和食物不能与墙壁或传送门重叠
+    pub fn validate(&self) -> Result<(), String> {
+        Editor::check_size(self.circus())?;
+
+        let blocked = |cell: &[i32; 2]| {
+            self.map.walls.contains(cell) || self.map.portals.iter().any(|pair| pair.contains(cell))
+        };
+        let snake = self.snake_cells();
+        if snake
+            .iter()
+            .any(|cell| !self.is_inside(*cell) || blocked(cell))
+        {
+            return Err(tr("The snake must start on free cells inside the border").to_string());
+        }
+        if let Some(fruit) = self.map.fruit {
+            if !self.is_inside(fruit) || blocked(&fruit) || snake.contains(&fruit) {
+                return Err(tr("The fruit must be on a free cell inside the border").to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 清空关卡，保留尺寸
+    pub fn clear(&mut self) {
+        self.map = Editor::blank(self.circus());
+        self.portal_start = None;
+    }
+
+    /// 按关卡中的出生位置和方向创建的蛇，用于预览和试玩
+    pub fn snake(&self) -> Snake {
+        self.map.snake(&self.circus())
+    }
+
+    // 关卡尺寸不能小于选项菜单中可选的最小尺寸
+    fn check_size(board_size: [u32; 2]) -> Result<(), String> {
+        let [min_width, min_height] = consts::BOARD_SIZES[0];
+        match board_size[0] < min_width || board_size[1] < min_height {
+            true => Err(trf(
+                "The level is smaller than {}",
+                &[&format!("{} x {}", min_width, min_height)],
+            )),
+            false => Ok(()),
+        }
+    }
+
+    /// 把关卡写入关卡文件
+    pub fn save(&mut self) {
+        self.message = Some(match self.map.save(&self.path) {
+            Ok(()) => trf("Saved to {}", &[&self.path.display()]),
+            Err(e) => trf("Failed to save: {}", &[&e]),
+        });
+    }
+
+    /// 从关卡文件读取关卡
+    pub fn load(&mut self) {
+        self.message = Some(match LevelMap::load(&self.path) {
+            Ok(mut map) => {
+                // 没有指定尺寸的关卡按当前尺寸编辑，太小的关卡无法编辑和试玩
+                if map.board_size.contains(&0) {
+                    map.board_size = self.circus();
+                }
+                match Editor::check_size(map.board_size) {
+                    Ok(()) => {
+                        self.map = map;
+                        self.portal_start = None;
+                        trf("Loaded {}", &[&self.path.display()])
+                    }
+                    Err(e) => trf("Failed to load: {}", &[&e]),
+                }
+            }
+            Err(e) => trf("Failed to load: {}", &[&e]),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blank() -> Editor {
+        Editor::new([40, 30], PathBuf::from("custom.toml"))
+    }
+
+    #[test]
+    fn spawn_and_fruit_clear_the_cell_under_them() {
+        let mut editor = blank();
+        editor.apply(Tool::Wall, (10, 10));
+        editor.apply(Tool::Wall, (11, 10));
+        editor.apply(Tool::Wall, (5, 5));
+
+        // 向左出发的蛇身体在蛇头右边，压住的墙壁都被擦除
+        editor.apply(Tool::Spawn, (10, 10));
+        editor.apply(Tool::Fruit, (5, 5));
+        assert_eq!(editor.map.walls, Vec::<[i32; 2]>::new());
+        assert_eq!(editor.map.fruit, Some([5, 5]));
+        assert_eq!(editor.validate(), Ok(()));
+
+        // 蛇占据的格子上不能再放墙壁和食物
+        editor.apply(Tool::Wall, (12, 10));
+        editor.apply(Tool::Fruit, (10, 10));
+        assert!(editor.map.walls.is_empty());
+        assert_eq!(editor.map.fruit, Some([5, 5]));
+    }
+
+    #[test]
+    fn both_portal_ends_erase_their_cells() {
+        let mut editor = blank();
+        editor.apply(Tool::Wall, (3, 3));
+        editor.apply(Tool::Fruit, (6, 6));
+
+        editor.apply(Tool::Portal, (3, 3));
+        editor.apply(Tool::Portal, (6, 6));
+        assert!(editor.map.walls.is_empty());
+        assert_eq!(editor.map.fruit, None);
+        assert_eq!(editor.map.portals, vec![[[3, 3], [6, 6]]]);
+    }
+
+    #[test]
+    fn unplayable_levels_are_reported() {
+        let mut editor = blank();
+        editor.map.walls.push([22, 15]);
+        assert!(editor.validate().is_err());
+
+        // 蛇身伸出边框
+        let mut editor = blank();
+        editor.map.spawn = Some([37, 15]);
+        assert!(editor.validate().is_err());
+
+        let mut editor = blank();
+        editor.map.board_size = [2, 2];
+        assert!(editor.validate().is_err());
+    }
+
+    #[test]
+    fn levels_smaller_than_the_smallest_board_are_not_loaded() {
+        let path = std::env::temp_dir().join(format!("snake-editor-{}.toml", std::process::id()));
+        std::fs::write(&path, "board_size = [2, 2]\nwalls = [[1, 1]]\n").unwrap();
+
+        let mut editor = Editor::new([40, 30], path.clone());
+        editor.load();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(editor.circus(), [40, 30]);
+        assert!(editor.map.walls.is_empty());
+    }
+}
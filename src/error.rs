@@ -0,0 +1,76 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// 程序中可能出现的错误，显示的信息会告诉玩家如何解决
+pub enum Error {
+    /// 无法创建窗口或OpenGL上下文
+    Window(String),
+    /// 资源文件无法读取或无法使用，包括资源名称和原因
+    Asset(String, String),
+    /// 设置文件无法解析，包括文件路径和原因
+    Config(PathBuf, String),
+    /// 存档或录像无法读写，包括文件路径和原因
+    Save(PathBuf, io::Error),
+    /// 找不到保存存档和录像的数据目录
+    NoDataDir,
+    /// 终端界面和无界面运行中的输入输出错误
+    Io(io::Error),
+}
+
+/// 使用[`Error`]的结果
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Window(reason) => write!(
+                f,
+                "failed to create the window: {}\n\
+                 Make sure a display is available and the graphics driver supports OpenGL 2.1 or newer",
+                reason
+            ),
+            Error::Asset(name, reason) => write!(
+                f,
+                "failed to load {}: {}\n\
+                 Set SNAKE_ASSETS to a directory containing a valid copy of it",
+                name, reason
+            ),
+            Error::Config(path, reason) => write!(
+                f,
+                "invalid settings file {}: {}\n\
+                 Fix or delete the file to restore the default settings",
+                path.display(),
+                reason
+            ),
+            Error::Save(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::NoDataDir => write!(
+                f,
+                "no data directory found, set HOME or XDG_DATA_HOME to a writable directory"
+            ),
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// `main`返回错误时用Debug格式打印，所以这里和Display一样显示可读的信息
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Save(_, e) | Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{powerup::PowerUpKind, Direction};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// 蛇撞上的障碍物
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Obstacle {
+    Body,   // 自己的身体
+    Border, // 边框
+    Wall,   // 墙壁
+}
+
+impl Obstacle {
+    /// 撞上这种障碍物时的死亡原因
+    pub fn cause(&self) -> &'static str {
+        match self {
+            Obstacle::Body => "Bit its own body",
+            Obstacle::Border => "Crashed into the border",
+            Obstacle::Wall => "Crashed into a wall",
+        }
+    }
+}
+
+/// 游戏过程中发生的事件
+/// 声音、统计、录像和外部程序都通过订阅事件了解游戏的进展
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GameEvent {
+    /// 新的一局开始，蛇头位于`head`
+    Started { head: (i32, i32) },
+    /// 蛇移动了一步，蛇头到达`head`
+    Moved { head: (i32, i32) },
+    /// 蛇改变了方向
+    Turned { from: Direction, to: Direction },
+    /// 在`cell`吃到了食物
+    AteFruit { cell: (i32, i32) },
+    /// 新的食物出现在`cell`
+    FruitSpawned { cell: (i32, i32) },
+    /// 食物被磁铁从`from`吸引到相邻的`to`
+    FruitMoved { from: (i32, i32), to: (i32, i32) },
+    /// `kind`道具出现在`cell`
+    PowerUpSpawned { kind: PowerUpKind, cell: (i32, i32) },
+    /// 在`cell`拾取了道具
+    PowerUpCollected { kind: PowerUpKind, cell: (i32, i32) },
+    /// 道具效果到期
+    PowerUpExpired { kind: PowerUpKind },
+    /// 生存模式中出现了占据`cells`的新墙壁
+    WallSpawned { cells: Vec<(i32, i32)> },
+    /// 破墙道具摧毁了`cell`的砖块
+    WallBroken { cell: (i32, i32) },
+    /// 禅模式中咬到自己，从`cell`截断后剩下`length`节
+    Trimmed { cell: (i32, i32), length: usize },
+    /// 在`cell`撞上了障碍物
+    Collided { obstacle: Obstacle, cell: (i32, i32) },
+    /// 游戏暂停
+    Paused,
+    /// 游戏继续
+    Resumed,
+    /// 完成了第`level`级
+    LevelCompleted { level: u32 },
+    /// 本局结束
+    Ended { score: u32, elapsed: f64 },
+}
+
+/// 事件的订阅者
+pub trait Subscriber {
+    /// 收到一个事件
+    fn notify(&mut self, event: &GameEvent);
+}
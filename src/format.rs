@@ -0,0 +1,153 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::io;
+
+/// 数据格式的版本，结构发生不兼容的变化时加一
+pub const VERSION: u32 = 1;
+
+/// 序列化的格式
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Format {
+    Json,   // 便于阅读和其他程序处理的JSON
+    Binary, // 紧凑的二进制格式
+}
+
+impl Format {
+    /// 文件的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Binary => "bin",
+        }
+    }
+}
+
+/// 带版本号的数据，版本号总是第一个字段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub version: u32, // 写入时的格式版本
+    pub data: T,      // 数据本身
+}
+
+// 只读取版本号，用来在解析数据之前检查版本
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+/// 按`format`序列化数据，并在前面加上当前的格式版本
+pub fn encode<T: Serialize>(data: &T, format: Format) -> io::Result<Vec<u8>> {
+    let versioned = Versioned {
+        version: VERSION,
+        data,
+    };
+
+    match format {
+        Format::Json => serde_json::to_vec_pretty(&versioned).map_err(invalid_data),
+        Format::Binary => bincode::serialize(&versioned).map_err(invalid_data),
+    }
+}
+
+/// 按`format`解析数据，版本与当前版本不同时返回错误
+pub fn decode<T: DeserializeOwned>(bytes: &[u8], format: Format) -> io::Result<T> {
+    let header: Header = match format {
+        Format::Json => serde_json::from_slice(bytes).map_err(invalid_data)?,
+        Format::Binary => bincode::deserialize(bytes).map_err(invalid_data)?,
+    };
+    if header.version != VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "unsupported format version {} (expected {})",
+                header.version, VERSION
+            ),
+        ));
+    }
+
+    let versioned: Versioned<T> = match format {
+        Format::Json => serde_json::from_slice(bytes).map_err(invalid_data)?,
+        Format::Binary => bincode::deserialize(bytes).map_err(invalid_data)?,
+    };
+
+    Ok(versioned.data)
+}
+
+// 把序列化的错误转换为IO错误
+fn invalid_data<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{consts, Block, Collited, Direction, Fruit, GameStatus, Snake, Wall};
+    use std::fmt::Debug;
+
+    // 按两种格式编码再解码，结果应该与原来的数据相同
+    fn round_trip<T>(data: T)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        for &format in [Format::Json, Format::Binary].iter() {
+            let bytes = encode(&data, format).unwrap();
+            let decoded: T = decode(&bytes, format).unwrap();
+            assert_eq!(decoded, data, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn entities_round_trip() {
+        round_trip(Block::new(3, 4, Collited::WithWall, consts::LIGHTBLUE));
+        round_trip(Wall::from_cells(&[(1, 1), (1, 2), (2, 2)]));
+        round_trip(Wall::board_wall(&[10, 8]));
+        round_trip(Fruit::at(5, 6));
+        round_trip(Snake::new(20, 16));
+    }
+
+    #[test]
+    fn enums_round_trip() {
+        round_trip(vec![
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ]);
+        round_trip(vec![
+            Collited::WithFruit,
+            Collited::WithSnake,
+            Collited::WithWall,
+            Collited::WithPowerUp,
+            Collited::NoCollision,
+        ]);
+        round_trip(vec![
+            GameStatus::MENU,
+            GameStatus::TIMEOUT,
+            GameStatus::RESUMING,
+            GameStatus::GAMING,
+            GameStatus::GAMEOVER,
+            GameStatus::RESTART,
+            GameStatus::EDITOR,
+            GameStatus::QUIT,
+        ]);
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let versioned = Versioned {
+            version: VERSION + 1,
+            data: Direction::Up,
+        };
+        let payloads = [
+            (Format::Json, serde_json::to_vec(&versioned).unwrap()),
+            (Format::Binary, bincode::serialize(&versioned).unwrap()),
+        ];
+
+        for (format, bytes) in payloads.iter() {
+            let error = decode::<Direction>(bytes, *format).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert!(error.to_string().contains("version"), "{}", error);
+        }
+    }
+}
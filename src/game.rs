@@ -0,0 +1,777 @@
+use crate::{
+    collision::{Collision, Collited},
+    consts,
+    event::{GameEvent, Obstacle},
+    obstacle::{DynamicWall, Portal},
+    powerup::{Effects, PowerUp, PowerUpKind},
+    Direction, Fruit, GameMode, GameStatus, Snake, Wall,
+};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// 一局开始时的布局，由关卡或编辑器中的地图决定
+pub struct Setup {
+    pub walls: Vec<Wall>,                // 墙壁
+    pub dynamic_walls: Vec<DynamicWall>, // 会随时间变化的墙壁
+    pub portals: Vec<Portal>,            // 传送门
+    pub snake: Snake,                    // 蛇蛇
+    pub fruit: Option<Fruit>,            // 食物，为空时随机放置
+}
+
+/// 一局游戏的规则，窗口、终端界面和无界面运行都由它推进
+/// 不依赖任何界面，发生的事件先排队，由界面取出后分发给订阅者
+/// 同一个种子总是生成同样的食物、道具和墙壁
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Game {
+    pub mode: GameMode,                  // 游戏模式
+    pub circus: [u32; 2],                // 移动空间
+    pub board_wall: Wall,                // 边框
+    pub walls: Vec<Wall>,                // 墙壁
+    pub dynamic_walls: Vec<DynamicWall>, // 会随时间变化的墙壁
+    pub portals: Vec<Portal>,            // 传送门
+    pub snake: Snake,                    // 蛇蛇
+    pub fruit: Fruit,                    // 食物
+    pub power_up: Option<PowerUp>,       // 场上的道具
+    pub effects: Effects,                // 生效中的道具效果
+    pub score: u32,                      // 分数
+    pub fruits: u32,                     // 吃到的食物数量
+    pub ticks: u64,                      // 经过的步数
+    pub elapsed: f64,                    // 已经进行的时间
+    pub next_wall: f64,                  // 生存模式中下一面墙壁出现的时间
+    pub seed: u64,                       // 生成关卡使用的种子
+    pub status: GameStatus,              // 进行中或已经结束
+    update_time: f64,                    // 距离上一步的时间
+    rng: Pcg64Mcg,                       // 生成食物、道具和墙壁使用的随机数生成器
+    #[cfg_attr(feature = "serde", serde(skip))]
+    events: Vec<GameEvent>, // 还没有被取出的事件
+}
+
+impl Game {
+    /// 在`circus`尺寸的移动空间中开始新的一局，墙壁和食物由`seed`决定
+    /// 和窗口中的随机关卡一样生成1到9面墙壁，终端界面和无界面运行使用它
+    pub fn new(mode: GameMode, circus: [u32; 2], seed: u64) -> Self {
+        let mut rng = Pcg64Mcg::seed_from_u64(seed);
+        let snake = Snake::new(circus[0], circus[1]);
+
+        // 墙壁不会挡在蛇出生的那一行
+        let spawn_row = snake.head.pos_y;
+        let mut walls = Vec::new();
+        for _ in 0..rng.gen_range(1, 10) {
+            let brick_num = rng.gen_range(5, 10);
+            let wall = Wall::randnew(Some(brick_num), &circus, &mut rng);
+            if wall.bricks.iter().all(|brick| brick.pos_y != spawn_row) {
+                walls.push(wall);
+            }
+        }
+
+        let setup = Setup {
+            walls,
+            dynamic_walls: Vec::new(),
+            portals: Vec::new(),
+            snake,
+            fruit: None,
+        };
+        Game::start(mode, circus, seed, rng, setup)
+    }
+
+    /// 按`setup`的布局开始新的一局，`rng`是生成布局之后的随机数生成器
+    /// 布局中没有食物时随机放置，开始和放置食物的事件在队列中等待取出
+    pub fn start(mode: GameMode, circus: [u32; 2], seed: u64, rng: Pcg64Mcg, setup: Setup) -> Self {
+        let mut game = Game {
+            mode,
+            circus,
+            board_wall: Wall::board_wall(&circus),
+            walls: setup.walls,
+            dynamic_walls: setup.dynamic_walls,
+            portals: setup.portals,
+            snake: setup.snake,
+            fruit: Fruit::at(1, 1),
+            power_up: None,
+            effects: Effects::new(),
+            score: 0,
+            fruits: 0,
+            ticks: 0,
+            elapsed: 0.0,
+            next_wall: consts::SURVIVAL_WALL_SECONDS,
+            seed,
+            status: GameStatus::GAMING,
+            update_time: 0.0,
+            rng,
+            events: Vec::new(),
+        };
+        game.fruit = match setup.fruit {
+            Some(fruit) => fruit,
+            None => game.spawn_fruit(),
+        };
+
+        game.emit(GameEvent::Started {
+            head: game.snake.head.cell(),
+        });
+        game.emit(GameEvent::FruitSpawned {
+            cell: game.fruit.block.cell(),
+        });
+
+        game
+    }
+
+    /// 取出队列中的事件
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    // 把事件放进队列
+    fn emit(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    /// 当前实际的速度，减速道具生效时变慢
+    pub fn velocity(&self) -> f64 {
+        match self.effects.is_active(PowerUpKind::SlowMotion) {
+            true => self.snake.velocity * consts::SLOW_MOTION_FACTOR,
+            false => self.snake.velocity,
+        }
+    }
+
+    /// 让蛇转向，不能掉头，方向改变时发出转向事件，成功时返回true
+    /// 每一步只能转向一次，否则一步之内连续转两次就会掉头撞上自己
+    pub fn turn(&mut self, to: Direction) -> bool {
+        let from = self.snake.direction.clone();
+        if self.status != GameStatus::GAMING
+            || self.snake.direction_lock
+            || to == from
+            || to == from.opposite()
+        {
+            return false;
+        }
+
+        self.snake.direction = to.clone();
+        self.snake.direction_lock = true;
+        self.emit(GameEvent::Turned { from, to });
+
+        true
+    }
+
+    /// 经过`dt`秒，到了蛇前进的时间就前进一步
+    /// 限时模式到时结束，生存模式定时增加墙壁
+    pub fn update(&mut self, dt: f64) {
+        if self.status != GameStatus::GAMING {
+            return;
+        }
+
+        // 积累下一次更新的时间和本局用时
+        self.update_time += dt;
+        self.elapsed += dt;
+
+        match self.mode {
+            GameMode::TimeAttack if self.elapsed >= consts::TIME_ATTACK_SECONDS => {
+                self.finish();
+                return;
+            }
+            GameMode::Survival if self.elapsed >= self.next_wall => {
+                self.spawn_survival_wall();
+                self.next_wall += consts::SURVIVAL_WALL_SECONDS;
+            }
+            _ => (),
+        }
+
+        // 我们以固定的时间间隔更新游戏的逻辑
+        if self.update_time >= 1.0 / self.velocity() {
+            self.update_time = 0.0;
+            self.step();
+        }
+    }
+
+    /// 前进一步：先处理蛇头所在格子上的碰撞，再移动蛇
+    /// 撞上障碍物时本局结束，禅模式不会结束
+    pub fn step(&mut self) {
+        if self.status != GameStatus::GAMING {
+            return;
+        }
+
+        // 解锁方向
+        self.snake.direction_lock = false;
+
+        // 碰撞检测
+        match self.collision() {
+            Collited::WithFruit => self.growth_action(),
+            Collited::WithPowerUp => self.collect_power_up(),
+            Collited::NoCollision => (),
+            cause if self.mode == GameMode::Zen => self.zen_collision(&cause),
+            cause => {
+                let obstacle = self.obstacle(&cause);
+                // 破墙道具生效时摧毁撞上的砖块，而不是结束本局
+                if obstacle != Obstacle::Wall || !self.break_wall() {
+                    self.emit(GameEvent::Collided {
+                        obstacle,
+                        cell: self.snake.head.cell(),
+                    });
+                    self.finish();
+                    return;
+                }
+            }
+        }
+
+        // 移动蛇
+        self.snake.moving();
+        self.enter_portal();
+        self.emit(GameEvent::Moved {
+            head: self.snake.head.cell(),
+        });
+
+        // 道具效果和场上的道具经过一步
+        self.tick_power_ups();
+
+        // 动态墙壁经过一步
+        self.ticks += 1;
+        for wall in self.dynamic_walls.iter_mut() {
+            wall.update(self.ticks, self.fruits, &self.snake);
+        }
+
+        // 目标模式达到目标长度就结束
+        if self.mode == GameMode::Target && self.snake.length() >= consts::TARGET_LENGTH {
+            self.finish();
+        }
+    }
+
+    /// 结束本局，禅模式不会死亡，由玩家主动结束
+    pub fn finish(&mut self) {
+        if self.status != GameStatus::GAMING {
+            return;
+        }
+
+        self.status = GameStatus::GAMEOVER;
+        self.emit(GameEvent::Ended {
+            score: self.score,
+            elapsed: self.elapsed,
+        });
+    }
+
+    // 蛇身加长的操作方法
+    fn growth_action(&mut self) {
+        self.emit(GameEvent::AteFruit {
+            cell: self.fruit.block.cell(),
+        });
+
+        // 调用蛇的方法
+        self.snake.growth_action();
+        // 增加分数，双倍得分道具生效时加倍
+        self.score += match self.effects.is_active(PowerUpKind::DoubleScore) {
+            true => 2,
+            false => 1,
+        };
+        self.fruits += 1;
+        // 选择一个不在墙壁中的随机位置，也可能在蛇上
+        self.fruit = self.spawn_fruit();
+        self.emit(GameEvent::FruitSpawned {
+            cell: self.fruit.block.cell(),
+        });
+
+        // 每吃到一定数量的食物就完成一级
+        if self.fruits.is_multiple_of(consts::FRUITS_PER_LEVEL) {
+            self.emit(GameEvent::LevelCompleted {
+                level: self.fruits / consts::FRUITS_PER_LEVEL,
+            });
+        }
+    }
+
+    // 拾取道具，开始计时它的效果
+    fn collect_power_up(&mut self) {
+        if let Some(power_up) = self.power_up.take() {
+            self.effects.activate(power_up.kind);
+            self.emit(GameEvent::PowerUpCollected {
+                kind: power_up.kind,
+                cell: power_up.block.cell(),
+            });
+        }
+    }
+
+    // 破墙道具生效时摧毁蛇头所在的砖块，返回是否摧毁了砖块
+    // 每个破墙道具只能摧毁一块砖
+    fn break_wall(&mut self) -> bool {
+        if !self.effects.is_active(PowerUpKind::WallBreaker) {
+            return false;
+        }
+
+        let (x, y) = self.snake.head.cell();
+        let broken = self.walls.iter_mut().any(|wall| wall.remove_brick(x, y))
+            || self
+                .dynamic_walls
+                .iter_mut()
+                .any(|dynamic| dynamic.solid && dynamic.wall.remove_brick(x, y));
+        if broken {
+            self.emit(GameEvent::WallBroken { cell: (x, y) });
+            self.effects.consume(PowerUpKind::WallBreaker);
+            self.emit(GameEvent::PowerUpExpired {
+                kind: PowerUpKind::WallBreaker,
+            });
+        }
+
+        broken
+    }
+
+    // 道具效果计时，场上的道具过一段时间消失，没有道具时随机出现新的道具
+    fn tick_power_ups(&mut self) {
+        for kind in self.effects.tick() {
+            self.emit(GameEvent::PowerUpExpired { kind });
+        }
+
+        if self.effects.is_active(PowerUpKind::Magnet) {
+            self.attract_fruit();
+        }
+
+        if let Some(power_up) = &mut self.power_up {
+            power_up.lifetime = power_up.lifetime.saturating_sub(1);
+            if power_up.lifetime == 0 {
+                self.power_up = None;
+            }
+        } else if self.rng.gen_bool(consts::POWER_UP_CHANCE) {
+            if let Some(power_up) = self.spawn_power_up() {
+                self.emit(GameEvent::PowerUpSpawned {
+                    kind: power_up.kind,
+                    cell: power_up.block.cell(),
+                });
+                self.power_up = Some(power_up);
+            }
+        }
+    }
+
+    // 在随机的空格子上创建道具，尝试有限的次数，找不到空格子时不创建
+    fn spawn_power_up(&mut self) -> Option<PowerUp> {
+        for _ in 0..100 {
+            let power_up = PowerUp::randnew(self.circus[0], self.circus[1], &mut self.rng);
+            if self.is_free(power_up.block.cell()) {
+                return Some(power_up);
+            }
+        }
+
+        None
+    }
+
+    // 磁铁把食物沿距离较远的方向向蛇头拉近一格，不会拉进墙壁、蛇身、传送门或道具
+    fn attract_fruit(&mut self) {
+        let head = self.snake.head.cell();
+        let fruit = self.fruit.block.cell();
+        let (dx, dy) = (head.0 - fruit.0, head.1 - fruit.1);
+        let target = match dx.abs() >= dy.abs() {
+            true => (fruit.0 + dx.signum(), fruit.1),
+            false => (fruit.0, fruit.1 + dy.signum()),
+        };
+        if target == fruit {
+            return;
+        }
+
+        let blocked = self.snake.body.iter().any(|block| block.cell() == target);
+        if !blocked && self.fits_fruit(target) {
+            self.fruit.block.pos_x = target.0;
+            self.fruit.block.pos_y = target.1;
+            self.emit(GameEvent::FruitMoved {
+                from: fruit,
+                to: target,
+            });
+        }
+    }
+
+    // 蛇头进入传送门时从另一端出来，保持原来的方向
+    fn enter_portal(&mut self) {
+        let (x, y) = self.snake.head.cell();
+        if let Some((x, y)) = self.portals.iter().find_map(|portal| portal.exit(x, y)) {
+            self.snake.teleport(x, y);
+        }
+    }
+
+    // 在随机位置创建食物，避开墙壁、传送门和道具，墙壁很密时尝试有限的次数
+    // 传送门上的食物永远吃不到，蛇头进入传送门的同一步就到了另一端
+    fn spawn_fruit(&mut self) -> Fruit {
+        let mut fruit = Fruit::randnew(self.circus[0], self.circus[1], &mut self.rng);
+        for _ in 0..100 {
+            if self.fits_fruit(fruit.block.cell()) {
+                break;
+            }
+            fruit = Fruit::randnew(self.circus[0], self.circus[1], &mut self.rng);
+        }
+
+        fruit
+    }
+
+    // 格子上是否有墙壁或者动态墙壁，动态墙壁暂时不会被撞上时也算
+    fn is_walled(&self, cell: (i32, i32)) -> bool {
+        self.walls
+            .iter()
+            .chain(self.dynamic_walls.iter().map(|dynamic| &dynamic.wall))
+            .flat_map(|wall| wall.bricks.iter())
+            .any(|brick| brick.cell() == cell)
+    }
+
+    // 格子上是否有传送门的一端
+    fn is_portal(&self, cell: (i32, i32)) -> bool {
+        self.portals
+            .iter()
+            .any(|portal| portal.cells().contains(&cell))
+    }
+
+    // 食物能否放在格子上：没有墙壁、传送门和道具，可以在蛇身上
+    fn fits_fruit(&self, cell: (i32, i32)) -> bool {
+        !self.is_walled(cell)
+            && !self.is_portal(cell)
+            && self
+                .power_up
+                .as_ref()
+                .is_none_or(|power_up| power_up.block.cell() != cell)
+    }
+
+    // 格子是否空着：没有墙壁，也没有蛇、食物、道具或者传送门
+    fn is_free(&self, cell: (i32, i32)) -> bool {
+        let taken = std::iter::once(&self.snake.head)
+            .chain(self.snake.body.iter())
+            .chain(std::iter::once(&self.fruit.block))
+            .chain(self.power_up.iter().map(|power_up| &power_up.block))
+            .any(|block| block.cell() == cell);
+
+        !taken && !self.is_walled(cell) && !self.is_portal(cell)
+    }
+
+    // 禅模式不会死亡：咬到自己截断尾巴，撞上边框从对面出来，直接穿过墙壁
+    fn zen_collision(&mut self, cause: &Collited) {
+        let (x, y) = self.snake.head.cell();
+        let (width, height) = (self.circus[0] as i32, self.circus[1] as i32);
+
+        match self.obstacle(cause) {
+            Obstacle::Body => {
+                self.snake.trim(x, y);
+                self.emit(GameEvent::Trimmed {
+                    cell: (x, y),
+                    length: self.snake.length(),
+                });
+            }
+            Obstacle::Border => {
+                let wrap = |value: i32, max: i32| match value {
+                    v if v <= 0 => max - 1,
+                    v if v >= max => 1,
+                    v => v,
+                };
+                self.snake.teleport(wrap(x, width), wrap(y, height));
+            }
+            Obstacle::Wall => (),
+        }
+    }
+
+    // 生存模式中增加一面墙壁，不会出现在蛇和食物上，也不会紧挨着蛇头
+    fn spawn_survival_wall(&mut self) {
+        let head = self.snake.head.cell();
+        let (width, height) = (self.circus[0] as i32, self.circus[1] as i32);
+
+        for _ in 0..100 {
+            let brick_num = self.rng.gen_range(3, 8);
+            let wall = Wall::randnew(Some(brick_num), &self.circus, &mut self.rng);
+
+            let safe = wall.bricks.iter().all(|brick| {
+                let (x, y) = brick.cell();
+                x > 0
+                    && y > 0
+                    && x < width
+                    && y < height
+                    && (x - head.0).abs() + (y - head.1).abs() > 5
+                    && self
+                        .snake
+                        .body
+                        .iter()
+                        .all(|block| block.is_collited_by_block(brick) == Collited::NoCollision)
+                    && self.fruit.is_collited_by_block(brick) == Collited::NoCollision
+            });
+            if safe {
+                let cells = wall.bricks.iter().map(|brick| brick.cell()).collect();
+                self.walls.push(wall);
+                self.emit(GameEvent::WallSpawned { cells });
+                return;
+            }
+        }
+    }
+
+    // 区分撞上的是身体、边框还是墙壁
+    fn obstacle(&self, cause: &Collited) -> Obstacle {
+        match cause {
+            Collited::WithSnake => Obstacle::Body,
+            _ if self.board_wall.is_collited_by_block(&self.snake.head) == Collited::WithWall => {
+                Obstacle::Border
+            }
+            _ => Obstacle::Wall,
+        }
+    }
+
+    // 蛇头碰到的东西
+    fn collision(&self) -> Collited {
+        let head = &self.snake.head;
+
+        // 碰撞到了身体？幽灵道具生效时可以穿过身体
+        if !self.effects.is_active(PowerUpKind::Ghost) && self.snake.is_colliting_with_self() {
+            return Collited::WithSnake;
+        }
+
+        // 碰撞到了边界、墙壁或者动态墙壁？
+        if self.board_wall.is_collited_by_block(head) == Collited::WithWall
+            || self
+                .walls
+                .iter()
+                .any(|wall| wall.is_collited_by_block(head) == Collited::WithWall)
+            || self
+                .dynamic_walls
+                .iter()
+                .any(|wall| wall.is_collited_by_block(head) == Collited::WithWall)
+        {
+            return Collited::WithWall;
+        }
+
+        // 碰撞到了食物？
+        if self.fruit.is_collited_by_block(head) == Collited::WithFruit {
+            return Collited::WithFruit;
+        }
+
+        // 碰撞到了道具？
+        match &self.power_up {
+            Some(power_up) => power_up.is_collited_by_block(head),
+            None => Collited::NoCollision,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 没有随机墙壁的一局，便于预测蛇的移动
+    fn open_game(mode: GameMode) -> Game {
+        let mut game = Game::new(mode, [20, 16], 1);
+        game.walls.clear();
+        game.fruit = Fruit::at(1, 1);
+        game.take_events();
+        game
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let a = Game::new(GameMode::Classic, [40, 30], 42);
+        let b = Game::new(GameMode::Classic, [40, 30], 42);
+
+        assert_eq!(a.walls, b.walls);
+        assert_eq!(a.fruit, b.fruit);
+    }
+
+    #[test]
+    fn walls_stay_off_the_spawn_row() {
+        for seed in 0..50 {
+            let game = Game::new(GameMode::Classic, [40, 30], seed);
+            let row = game.snake.head().cell().1;
+            assert!(game
+                .walls
+                .iter()
+                .flat_map(|wall| wall.bricks())
+                .all(|brick| brick.cell().1 != row));
+        }
+    }
+
+    #[test]
+    fn starting_queues_the_start_and_the_fruit() {
+        let mut game = Game::new(GameMode::Classic, [40, 30], 3);
+        let head = game.snake.head().cell();
+        let fruit = game.fruit.block().cell();
+
+        assert_eq!(
+            game.take_events(),
+            vec![
+                GameEvent::Started { head },
+                GameEvent::FruitSpawned { cell: fruit }
+            ]
+        );
+        assert!(game.take_events().is_empty());
+    }
+
+    #[test]
+    fn turns_once_per_step_and_never_back() {
+        let mut game = open_game(GameMode::Classic);
+
+        assert!(!game.turn(Direction::Right));
+        assert!(game.turn(Direction::Up));
+        assert!(!game.turn(Direction::Left));
+        assert_eq!(game.snake.direction(), &Direction::Up);
+
+        game.step();
+        assert!(game.turn(Direction::Left));
+    }
+
+    #[test]
+    fn turning_the_same_way_keeps_the_turn_free() {
+        let mut game = open_game(GameMode::Classic);
+
+        assert!(!game.turn(Direction::Left));
+        assert!(game.take_events().is_empty());
+        assert!(game.turn(Direction::Up));
+        assert_eq!(
+            game.take_events(),
+            vec![GameEvent::Turned {
+                from: Direction::Left,
+                to: Direction::Up
+            }]
+        );
+    }
+
+    #[test]
+    fn collisions_are_checked_before_moving() {
+        let mut game = open_game(GameMode::Classic);
+        let (x, y) = game.snake.head().cell();
+        game.fruit = Fruit::at(x - 1, y);
+
+        // 第一步走到食物上，下一步开始时才吃到
+        game.step();
+        assert_eq!(game.snake.head().cell(), (x - 1, y));
+        assert_eq!(game.score, 0);
+
+        game.step();
+        assert_eq!(game.score, 1);
+        assert_eq!(game.ticks, 2);
+        assert!(game
+            .take_events()
+            .contains(&GameEvent::AteFruit { cell: (x - 1, y) }));
+
+        game.step();
+        assert_eq!(game.snake.length(), 6);
+    }
+
+    #[test]
+    fn crashing_into_the_border_ends_the_game() {
+        let mut game = open_game(GameMode::Classic);
+        let (x, y) = game.snake.head().cell();
+
+        // 蛇头到达边框之后的下一步才撞上
+        for _ in 0..=x {
+            game.step();
+        }
+        assert_eq!(game.status, GameStatus::GAMEOVER);
+        assert_eq!(game.snake.head().cell(), (0, y));
+        let events = game.take_events();
+        assert!(events.contains(&GameEvent::Collided {
+            obstacle: Obstacle::Border,
+            cell: (0, y)
+        }));
+        assert!(matches!(events.last(), Some(GameEvent::Ended { .. })));
+
+        game.step();
+        assert!(game.take_events().is_empty());
+        assert!(!game.turn(Direction::Up));
+    }
+
+    #[test]
+    fn zen_wraps_around_the_border() {
+        let mut game = open_game(GameMode::Zen);
+        let (x, y) = game.snake.head().cell();
+
+        for _ in 0..=x {
+            game.step();
+        }
+        assert_eq!(game.status, GameStatus::GAMING);
+        assert_eq!(game.snake.head().cell(), (game.circus[0] as i32 - 2, y));
+    }
+
+    #[test]
+    fn time_attack_ends_when_time_is_up() {
+        let mut game = open_game(GameMode::TimeAttack);
+
+        game.update(consts::TIME_ATTACK_SECONDS - 1.0);
+        assert_eq!(game.status, GameStatus::GAMING);
+        game.update(1.0);
+        assert_eq!(game.status, GameStatus::GAMEOVER);
+    }
+
+    #[test]
+    fn steps_follow_the_speed() {
+        let mut game = open_game(GameMode::Classic);
+        let interval = 1.0 / game.velocity();
+
+        game.update(interval * 0.5);
+        assert_eq!(game.ticks, 0);
+        game.update(interval * 0.5);
+        assert_eq!(game.ticks, 1);
+    }
+
+    #[test]
+    fn power_ups_only_appear_on_free_cells() {
+        let mut game = open_game(GameMode::Classic);
+        // 只留下一个空格子：墙壁、食物和传送门占满了其他格子
+        let (width, height) = (game.circus[0] as i32, game.circus[1] as i32);
+        let mut taken: Vec<(i32, i32)> = std::iter::once(&game.snake.head)
+            .chain(game.snake.body.iter())
+            .map(|block| block.cell())
+            .collect();
+        taken.extend([(1, 1), (2, 1), (3, 1)]);
+        let cells: Vec<(i32, i32)> = (1..width - 1)
+            .flat_map(|x| (1..height - 1).map(move |y| (x, y)))
+            .filter(|cell| !taken.contains(cell))
+            .collect();
+        let (free, walled) = cells.split_last().unwrap();
+        game.walls = vec![Wall::from_cells(walled)];
+        game.portals = vec![Portal::new((2, 1), (3, 1))];
+
+        for _ in 0..50 {
+            if let Some(power_up) = game.spawn_power_up() {
+                assert_eq!(power_up.block.cell(), *free);
+            }
+        }
+    }
+
+    #[test]
+    fn fruit_stays_off_portals_and_power_ups() {
+        let mut game = open_game(GameMode::Classic);
+        // 墙壁填满最上面两行以外的格子，传送门和道具占据其中三个格子
+        let (width, height) = (game.circus[0] as i32, game.circus[1] as i32);
+        let walled: Vec<(i32, i32)> = (1..width - 1)
+            .flat_map(|x| (3..height - 1).map(move |y| (x, y)))
+            .collect();
+        game.walls = vec![Wall::from_cells(&walled)];
+        game.snake = Snake::new(width as u32, 4);
+        game.portals = vec![Portal::new((5, 1), (6, 2))];
+        let mut power_up = PowerUp::randnew(game.circus[0], game.circus[1], &mut game.rng);
+        (power_up.block.pos_x, power_up.block.pos_y) = (7, 1);
+        game.power_up = Some(power_up);
+
+        for _ in 0..100 {
+            let cell = game.spawn_fruit().block().cell();
+            assert!(cell.1 < 3);
+            assert!(![(5, 1), (6, 2), (7, 1)].contains(&cell));
+        }
+    }
+
+    #[test]
+    fn magnets_do_not_pull_fruit_onto_portals_or_power_ups() {
+        let mut game = open_game(GameMode::Classic);
+        let (x, y) = game.snake.head().cell();
+        game.effects.activate(PowerUpKind::Magnet);
+        game.fruit = Fruit::at(x - 5, y);
+        game.portals = vec![Portal::new((x - 4, y), (3, 3))];
+
+        game.attract_fruit();
+        assert_eq!(game.fruit.block().cell(), (x - 5, y));
+
+        game.portals.clear();
+        let mut power_up = PowerUp::randnew(game.circus[0], game.circus[1], &mut game.rng);
+        (power_up.block.pos_x, power_up.block.pos_y) = (x - 4, y);
+        game.power_up = Some(power_up);
+        game.attract_fruit();
+        assert_eq!(game.fruit.block().cell(), (x - 5, y));
+
+        game.power_up = None;
+        game.attract_fruit();
+        assert_eq!(game.fruit.block().cell(), (x - 4, y));
+    }
+
+    #[test]
+    fn portals_carry_the_snake_to_the_other_end() {
+        let mut game = open_game(GameMode::Classic);
+        let (x, y) = game.snake.head().cell();
+        game.portals = vec![Portal::new((x - 1, y), (5, 12))];
+
+        game.step();
+        assert_eq!(game.snake.head().cell(), (5, 12));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{settings::GamepadSettings, Direction};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 手柄输入转换成的操作
+#[derive(Debug, PartialEq, Clone)]
+pub enum PadAction {
+    Steer(Direction), // 游戏中转向，菜单中移动选择
+    Start,            // 暂停或继续
+    Confirm,          // 选择菜单项
+    Back,             // 返回上一级菜单
+}
+
+/// 可以映射到操作的手柄按钮，按标准手柄的布局命名
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum PadButton {
+    South,        // 下方的动作键，Xbox手柄的A
+    East,         // 右方的动作键，Xbox手柄的B
+    North,        // 上方的动作键，Xbox手柄的Y
+    West,         // 左方的动作键，Xbox手柄的X
+    Select,       // 左侧的菜单键
+    Start,        // 右侧的菜单键
+    LeftTrigger,  // 左肩键
+    RightTrigger, // 右肩键
+}
+
+/// 所有手柄的输入，通过gilrs读取，窗口后端不需要支持手柄
+/// 没有启用`gamepad`特性或系统不支持手柄时不产生任何操作
+#[derive(Default)]
+pub struct Gamepads {
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<gilrs::Gilrs>, // 手柄事件的来源
+    sticks: HashMap<usize, [f64; 2]>,        // 每个手柄左摇杆的位置
+    held: HashMap<usize, Option<Direction>>, // 每个手柄左摇杆当前指向的方向
+}
+
+impl Gamepads {
+    /// 开始读取手柄，无法读取时只打印警告
+    pub fn new() -> Self {
+        #[cfg(feature = "gamepad")]
+        {
+            match gilrs::Gilrs::new() {
+                Ok(gilrs) => {
+                    return Gamepads {
+                        gilrs: Some(gilrs),
+                        ..Gamepads::default()
+                    }
+                }
+                Err(e) => eprintln!("Gamepads are unavailable: {}", e),
+            }
+        }
+
+        Gamepads::default()
+    }
+
+    /// 取出上次调用以来所有手柄产生的操作，以及产生操作的手柄编号
+    #[cfg(feature = "gamepad")]
+    pub fn poll(&mut self, settings: &GamepadSettings) -> Vec<(usize, PadAction)> {
+        let mut actions = Vec::new();
+
+        while let Some(event) = self.gilrs.as_mut().and_then(|gilrs| gilrs.next_event()) {
+            let id = usize::from(event.id);
+            if let Some(action) = self.event(settings, id, event.event) {
+                actions.push((id, action));
+            }
+        }
+
+        actions
+    }
+
+    /// 没有启用`gamepad`特性时不会有任何操作
+    #[cfg(not(feature = "gamepad"))]
+    pub fn poll(&mut self, _settings: &GamepadSettings) -> Vec<(usize, PadAction)> {
+        Vec::new()
+    }
+
+    // 把gilrs的事件转换成操作，gilrs的纵轴向上为正
+    #[cfg(feature = "gamepad")]
+    fn event(
+        &mut self,
+        settings: &GamepadSettings,
+        id: usize,
+        event: gilrs::EventType,
+    ) -> Option<PadAction> {
+        use gilrs::{Axis, Button, EventType};
+
+        match event {
+            EventType::ButtonPressed(Button::DPadUp, _) => Some(PadAction::Steer(Direction::Up)),
+            EventType::ButtonPressed(Button::DPadDown, _) => {
+                Some(PadAction::Steer(Direction::Down))
+            }
+            EventType::ButtonPressed(Button::DPadLeft, _) => {
+                Some(PadAction::Steer(Direction::Left))
+            }
+            EventType::ButtonPressed(Button::DPadRight, _) => {
+                Some(PadAction::Steer(Direction::Right))
+            }
+            EventType::ButtonPressed(button, _) => {
+                let button = match button {
+                    Button::South => PadButton::South,
+                    Button::East => PadButton::East,
+                    Button::North => PadButton::North,
+                    Button::West => PadButton::West,
+                    Button::Select => PadButton::Select,
+                    Button::Start => PadButton::Start,
+                    Button::LeftTrigger => PadButton::LeftTrigger,
+                    Button::RightTrigger => PadButton::RightTrigger,
+                    _ => return None,
+                };
+                Gamepads::button(settings, button)
+            }
+            EventType::AxisChanged(Axis::LeftStickX, value, _) => {
+                self.stick(settings, id, 0, value as f64)
+            }
+            EventType::AxisChanged(Axis::LeftStickY, value, _) => {
+                self.stick(settings, id, 1, -value as f64)
+            }
+            // 部分手柄把方向键报告为轴
+            EventType::AxisChanged(Axis::DPadX, value, _) if value.abs() > 0.5 => {
+                match value > 0.0 {
+                    true => Some(PadAction::Steer(Direction::Right)),
+                    false => Some(PadAction::Steer(Direction::Left)),
+                }
+            }
+            EventType::AxisChanged(Axis::DPadY, value, _) if value.abs() > 0.5 => {
+                match value > 0.0 {
+                    true => Some(PadAction::Steer(Direction::Up)),
+                    false => Some(PadAction::Steer(Direction::Down)),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// 按下手柄按钮，没有映射到操作的按钮返回None
+    pub fn button(settings: &GamepadSettings, button: PadButton) -> Option<PadAction> {
+        match button {
+            b if b == settings.start => Some(PadAction::Start),
+            b if b == settings.confirm => Some(PadAction::Confirm),
+            b if b == settings.back => Some(PadAction::Back),
+            _ => None,
+        }
+    }
+
+    /// 移动`id`号手柄的左摇杆，`axis`为0是横轴，为1是纵轴，纵轴向下为正
+    /// 摇杆离开死区时按偏移较大的轴决定方向，方向改变时才产生操作，回到死区后可以再次触发
+    pub fn stick(
+        &mut self,
+        settings: &GamepadSettings,
+        id: usize,
+        axis: usize,
+        position: f64,
+    ) -> Option<PadAction> {
+        let stick = self.sticks.entry(id).or_insert([0.0, 0.0]);
+        stick[axis.min(1)] = position;
+
+        let [x, y] = *stick;
+        let direction = match x.abs().max(y.abs()) > settings.deadzone {
+            false => None,
+            true if x.abs() > y.abs() && x > 0.0 => Some(Direction::Right),
+            true if x.abs() > y.abs() => Some(Direction::Left),
+            true if y > 0.0 => Some(Direction::Down),
+            true => Some(Direction::Up),
+        };
+
+        let held = self.held.entry(id).or_insert(None);
+        if *held == direction {
+            return None;
+        }
+        *held = direction.clone();
+
+        direction.map(PadAction::Steer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stick_turns_once_per_direction() {
+        let settings = GamepadSettings::default();
+        let mut pads = Gamepads::default();
+
+        assert_eq!(pads.stick(&settings, 0, 0, 0.1), None);
+        assert_eq!(
+            pads.stick(&settings, 0, 0, 0.8),
+            Some(PadAction::Steer(Direction::Right))
+        );
+        assert_eq!(pads.stick(&settings, 0, 0, 0.9), None);
+        assert_eq!(
+            pads.stick(&settings, 0, 1, -0.95),
+            Some(PadAction::Steer(Direction::Up))
+        );
+
+        // 回到死区后可以再次触发
+        pads.stick(&settings, 0, 0, 0.0);
+        assert_eq!(pads.stick(&settings, 0, 1, 0.0), None);
+        assert_eq!(
+            pads.stick(&settings, 0, 1, -0.95),
+            Some(PadAction::Steer(Direction::Up))
+        );
+    }
+
+    #[test]
+    fn sticks_are_tracked_per_controller() {
+        let settings = GamepadSettings::default();
+        let mut pads = Gamepads::default();
+
+        pads.stick(&settings, 0, 0, 0.8);
+        assert_eq!(
+            pads.stick(&settings, 1, 0, 0.8),
+            Some(PadAction::Steer(Direction::Right))
+        );
+    }
+
+    #[test]
+    fn buttons_follow_the_mapping() {
+        let mut settings = GamepadSettings::default();
+        assert_eq!(
+            Gamepads::button(&settings, PadButton::Start),
+            Some(PadAction::Start)
+        );
+        assert_eq!(Gamepads::button(&settings, PadButton::North), None);
+
+        settings.confirm = PadButton::North;
+        assert_eq!(
+            Gamepads::button(&settings, PadButton::North),
+            Some(PadAction::Confirm)
+        );
+    }
+}
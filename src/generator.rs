@@ -0,0 +1,488 @@
+use crate::Wall;
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// 程序化生成关卡的生成器
+/// 同样的生成器、尺寸和种子总是生成同样的关卡
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Generator {
+    Rooms, // 由走廊连接的房间
+    Arena, // 上下左右对称的竞技场
+    Maze,  // 递归回溯生成的迷宫
+    Caves, // 元胞自动机生成的洞穴
+    Rings, // 带缺口的同心环
+}
+
+impl Generator {
+    /// 所有生成器
+    pub const ALL: [Generator; 5] = [
+        Generator::Rooms,
+        Generator::Arena,
+        Generator::Maze,
+        Generator::Caves,
+        Generator::Rings,
+    ];
+
+    /// 生成器名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Generator::Rooms => "Rooms",
+            Generator::Arena => "Arena",
+            Generator::Maze => "Maze",
+            Generator::Caves => "Caves",
+            Generator::Rings => "Rings",
+        }
+    }
+
+    /// 用`rng`生成`circus`尺寸的移动空间中的墙壁
+    /// 所有空格子互相连通，蛇出生的位置周围没有墙壁
+    pub fn generate(&self, circus: &[u32; 2], rng: &mut Pcg64Mcg) -> Vec<Wall> {
+        let mut grid = Grid::new(circus);
+
+        match self {
+            Generator::Rooms => grid.rooms(rng),
+            Generator::Arena => grid.arena(rng),
+            Generator::Maze => grid.maze(rng),
+            Generator::Caves => grid.caves(rng),
+            Generator::Rings => grid.rings(rng),
+        }
+
+        grid.clear_spawn();
+        grid.connect();
+
+        match grid.walls() {
+            cells if cells.is_empty() => Vec::new(),
+            cells => vec![Wall::from_cells(&cells)],
+        }
+    }
+}
+
+/// 生成关卡用的格子，边框上的格子总是墙壁
+struct Grid {
+    width: i32,       // 与移动空间相同，边框位于坐标0和`width`
+    height: i32,      // 与移动空间相同，边框位于坐标0和`height`
+    walls: Vec<bool>, // 每个格子是否是墙壁
+}
+
+impl Grid {
+    fn new(circus: &[u32; 2]) -> Self {
+        let width = circus[0] as i32;
+        let height = circus[1] as i32;
+
+        Grid {
+            width,
+            height,
+            walls: vec![false; ((width + 1) * (height + 1)) as usize],
+        }
+    }
+
+    // 格子是否在边框以内
+    fn inside(&self, x: i32, y: i32) -> bool {
+        x > 0 && y > 0 && x < self.width && y < self.height
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        (y * (self.width + 1) + x) as usize
+    }
+
+    // 边框和边框之外都算作墙壁
+    fn is_wall(&self, x: i32, y: i32) -> bool {
+        !self.inside(x, y) || self.walls[self.index(x, y)]
+    }
+
+    // 修改边框以内的格子，边框和边框之外的格子不变
+    fn set(&mut self, x: i32, y: i32, wall: bool) {
+        if self.inside(x, y) {
+            let index = self.index(x, y);
+            self.walls[index] = wall;
+        }
+    }
+
+    // 修改一个矩形区域内的格子
+    fn fill(&mut self, x: i32, y: i32, width: i32, height: i32, wall: bool) {
+        for j in y..y + height {
+            for i in x..x + width {
+                self.set(i, j, wall);
+            }
+        }
+    }
+
+    // 边框以内的所有格子
+    fn cells(&self) -> Vec<(i32, i32)> {
+        (1..self.height)
+            .flat_map(|y| (1..self.width).map(move |x| (x, y)))
+            .collect()
+    }
+
+    // 所有墙壁格子
+    fn walls(&self) -> Vec<(i32, i32)> {
+        self.cells()
+            .into_iter()
+            .filter(|&(x, y)| self.is_wall(x, y))
+            .collect()
+    }
+
+    // 蛇出生时蛇头的位置，与`Snake::new`一致
+    fn spawn(&self) -> (i32, i32) {
+        (
+            ((self.width as f64) * 0.5) as i32,
+            ((self.height as f64) * 0.5) as i32,
+        )
+    }
+
+    // 清空蛇出生位置周围的区域，蛇身向右延伸，蛇头向左留出转向的空间
+    fn clear_spawn(&mut self) {
+        let (x, y) = self.spawn();
+        self.fill(x - 8, y - 1, 14, 3, false);
+    }
+
+    // 房间和走廊：在实心的场地中挖出不重叠的房间，再用两格宽的走廊依次连接
+    fn rooms(&mut self, rng: &mut Pcg64Mcg) {
+        self.fill(1, 1, self.width - 1, self.height - 1, true);
+
+        let mut rooms: Vec<[i32; 4]> = Vec::new();
+        let max_rooms = ((self.width * self.height) / 250).max(2);
+        for _ in 0..max_rooms * 4 {
+            if rooms.len() as i32 >= max_rooms {
+                break;
+            }
+
+            let w = rng.gen_range(5, 14.min(self.width - 2).max(6));
+            let h = rng.gen_range(4, 10.min(self.height - 2).max(5));
+            let x = rng.gen_range(1, (self.width - w).max(2));
+            let y = rng.gen_range(1, (self.height - h).max(2));
+            let room = [x, y, w, h];
+
+            // 房间之间至少隔一格墙壁
+            let overlaps = rooms.iter().any(|other| {
+                x <= other[0] + other[2]
+                    && other[0] <= x + w
+                    && y <= other[1] + other[3]
+                    && other[1] <= y + h
+            });
+            if !overlaps {
+                rooms.push(room);
+            }
+        }
+
+        for room in rooms.iter() {
+            self.fill(room[0], room[1], room[2], room[3], false);
+        }
+
+        // 按顺序连接相邻两个房间的中心
+        for pair in rooms.windows(2) {
+            let a = (pair[0][0] + pair[0][2] / 2, pair[0][1] + pair[0][3] / 2);
+            let b = (pair[1][0] + pair[1][2] / 2, pair[1][1] + pair[1][3] / 2);
+
+            let (left, right) = (a.0.min(b.0), a.0.max(b.0));
+            let (top, bottom) = (a.1.min(b.1), a.1.max(b.1));
+            match rng.gen_bool(0.5) {
+                true => {
+                    self.fill(left, a.1, right - left + 2, 2, false);
+                    self.fill(b.0, top, 2, bottom - top + 2, false);
+                }
+                false => {
+                    self.fill(a.0, top, 2, bottom - top + 2, false);
+                    self.fill(left, b.1, right - left + 2, 2, false);
+                }
+            }
+        }
+    }
+
+    // 对称竞技场：在左上角放置随机的短墙，再镜像到其他三个角
+    fn arena(&mut self, rng: &mut Pcg64Mcg) {
+        let half_width = self.width / 2;
+        let half_height = self.height / 2;
+        let pieces = ((self.width * self.height) / 300).max(2);
+
+        for _ in 0..pieces {
+            let len = rng.gen_range(2, 7);
+            let (w, h) = match rng.gen_bool(0.5) {
+                true => (len, 1),
+                false => (1, len),
+            };
+            let x = rng.gen_range(2, (half_width - w).max(3));
+            let y = rng.gen_range(2, (half_height - h).max(3));
+
+            for j in y..y + h {
+                for i in x..x + w {
+                    self.set(i, j, true);
+                    self.set(self.width - i, j, true);
+                    self.set(i, self.height - j, true);
+                    self.set(self.width - i, self.height - j, true);
+                }
+            }
+        }
+    }
+
+    // 迷宫：每个迷宫单元是2x2的通道加一格墙壁，用递归回溯挖出通道
+    fn maze(&mut self, rng: &mut Pcg64Mcg) {
+        self.fill(1, 1, self.width - 1, self.height - 1, true);
+
+        let columns = (self.width / 3).max(1);
+        let rows = (self.height / 3).max(1);
+        let origin = |cell: (i32, i32)| (1 + cell.0 * 3, 1 + cell.1 * 3);
+
+        let mut visited = vec![false; (columns * rows) as usize];
+        let mut stack = vec![(0, 0)];
+        visited[0] = true;
+        let (x, y) = origin((0, 0));
+        self.fill(x, y, 2, 2, false);
+
+        // 用显式的栈代替递归，避免大场地上栈溢出
+        while let Some(&(cx, cy)) = stack.last() {
+            let neighbours: Vec<(i32, i32)> = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .iter()
+                .map(|&(dx, dy)| (cx + dx, cy + dy))
+                .filter(|&(nx, ny)| {
+                    nx >= 0
+                        && ny >= 0
+                        && nx < columns
+                        && ny < rows
+                        && !visited[(ny * columns + nx) as usize]
+                })
+                .collect();
+
+            if neighbours.is_empty() {
+                stack.pop();
+                continue;
+            }
+
+            let next = neighbours[rng.gen_range(0, neighbours.len())];
+            visited[(next.1 * columns + next.0) as usize] = true;
+
+            // 挖开两个单元以及它们之间的墙壁
+            let (ax, ay) = origin((cx, cy));
+            let (bx, by) = origin(next);
+            let (x, y) = (ax.min(bx), ay.min(by));
+            self.fill(x, y, (ax - bx).abs() + 2, (ay - by).abs() + 2, false);
+
+            stack.push(next);
+        }
+    }
+
+    // 洞穴：随机填充后用元胞自动机平滑，周围墙壁多的格子变成墙壁
+    fn caves(&mut self, rng: &mut Pcg64Mcg) {
+        for (x, y) in self.cells() {
+            let wall = rng.gen_bool(0.45);
+            self.set(x, y, wall);
+        }
+
+        for _ in 0..5 {
+            let next: Vec<((i32, i32), bool)> = self
+                .cells()
+                .into_iter()
+                .map(|(x, y)| {
+                    let walls = (-1..=1)
+                        .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                        .filter(|&(dx, dy)| (dx, dy) != (0, 0) && self.is_wall(x + dx, y + dy))
+                        .count();
+                    ((x, y), walls >= 5)
+                })
+                .collect();
+
+            for ((x, y), wall) in next {
+                self.set(x, y, wall);
+            }
+        }
+    }
+
+    // 同心环：从外向内每隔几格画一圈墙壁，每圈留出几个缺口
+    fn rings(&mut self, rng: &mut Pcg64Mcg) {
+        let spacing = 5;
+        let mut inset = spacing;
+
+        while self.width - inset * 2 > spacing && self.height - inset * 2 > spacing {
+            let (left, top) = (inset, inset);
+            let (right, bottom) = (self.width - inset, self.height - inset);
+
+            // 沿着环走一圈，记录每个格子
+            let mut ring = Vec::new();
+            ring.extend((left..right).map(|x| (x, top)));
+            ring.extend((top..bottom).map(|y| (right, y)));
+            ring.extend((left + 1..=right).rev().map(|x| (x, bottom)));
+            ring.extend((top + 1..=bottom).rev().map(|y| (left, y)));
+
+            for &(x, y) in ring.iter() {
+                self.set(x, y, true);
+            }
+
+            // 每个缺口3格宽，均匀分布在环上，起点随机
+            let gaps = rng.gen_range(2, 5);
+            let start = rng.gen_range(0, ring.len());
+            for gap in 0..gaps {
+                let offset = start + gap * ring.len() / gaps;
+                for i in 0..3 {
+                    let (x, y) = ring[(offset + i) % ring.len()];
+                    self.set(x, y, false);
+                }
+            }
+
+            inset += spacing;
+        }
+    }
+
+    // 从`start`出发能够到达的空格子
+    fn flood(&self, start: (i32, i32)) -> Vec<bool> {
+        let mut reached = vec![false; self.walls.len()];
+        let mut queue = VecDeque::new();
+        if !self.is_wall(start.0, start.1) {
+            reached[self.index(start.0, start.1)] = true;
+            queue.push_back(start);
+        }
+
+        while let Some((x, y)) = queue.pop_front() {
+            for &(dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+                let (nx, ny) = (x + dx, y + dy);
+                if !self.is_wall(nx, ny) && !reached[self.index(nx, ny)] {
+                    reached[self.index(nx, ny)] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        reached
+    }
+
+    // 保证所有空格子都与出生位置连通
+    // 每次找到一块不连通的区域，挖出一条穿过墙壁到达出生区域的最短通道
+    fn connect(&mut self) {
+        let spawn = self.spawn();
+
+        loop {
+            let main = self.flood(spawn);
+            let isolated = self
+                .cells()
+                .into_iter()
+                .find(|&(x, y)| !self.is_wall(x, y) && !main[self.index(x, y)]);
+            let isolated = match isolated {
+                Some(cell) => cell,
+                None => return,
+            };
+
+            // 从这块区域的所有格子同时出发，穿过墙壁寻找出生区域
+            let region = self.flood(isolated);
+            let mut previous: Vec<Option<(i32, i32)>> = vec![None; self.walls.len()];
+            let mut seen = region.clone();
+            let mut queue: VecDeque<(i32, i32)> = self
+                .cells()
+                .into_iter()
+                .filter(|&(x, y)| region[self.index(x, y)])
+                .collect();
+
+            let mut target = None;
+            while let Some((x, y)) = queue.pop_front() {
+                if main[self.index(x, y)] {
+                    target = Some((x, y));
+                    break;
+                }
+                for &(dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if self.inside(nx, ny) && !seen[self.index(nx, ny)] {
+                        seen[self.index(nx, ny)] = true;
+                        previous[self.index(nx, ny)] = Some((x, y));
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+
+            // 找不到出生区域时把这块区域填成墙壁
+            if target.is_none() {
+                for (x, y) in self.cells() {
+                    if region[self.index(x, y)] {
+                        self.set(x, y, true);
+                    }
+                }
+            }
+
+            // 沿着来路挖开通道
+            let mut cell = target;
+            while let Some((x, y)) = cell {
+                self.set(x, y, false);
+                cell = previous[self.index(x, y)];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Snake;
+    use rand::SeedableRng;
+    use std::collections::HashSet;
+
+    const SIZES: [[u32; 2]; 5] = [[20, 16], [40, 30], [60, 45], [80, 60], [100, 75]];
+
+    // 从蛇头出发能够到达的所有空格子
+    fn reachable(circus: &[u32; 2], walls: &HashSet<(i32, i32)>, start: (i32, i32)) -> usize {
+        let inside =
+            |(x, y): (i32, i32)| x > 0 && y > 0 && x < circus[0] as i32 && y < circus[1] as i32;
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        seen.insert(start);
+        queue.push_back(start);
+
+        while let Some((x, y)) = queue.pop_front() {
+            for &(dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+                let next = (x + dx, y + dy);
+                if inside(next) && !walls.contains(&next) && seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        seen.len()
+    }
+
+    #[test]
+    fn every_floor_cell_is_reachable_and_spawn_is_clear() {
+        for &generator in Generator::ALL.iter() {
+            for circus in SIZES.iter() {
+                for seed in 0..4 {
+                    let walls: HashSet<(i32, i32)> = generator
+                        .generate(circus, &mut Pcg64Mcg::seed_from_u64(seed))
+                        .iter()
+                        .flat_map(|wall| wall.bricks().iter().map(|brick| brick.cell()))
+                        .collect();
+                    let snake = Snake::new(circus[0], circus[1]);
+                    let head = snake.head().cell();
+                    let case = format!("{:?} {:?} seed {}", generator, circus, seed);
+
+                    for block in std::iter::once(snake.head()).chain(snake.body()) {
+                        assert!(!walls.contains(&block.cell()), "snake blocked: {}", case);
+                    }
+                    for x in head.0 - 8..head.0 {
+                        assert!(!walls.contains(&(x, head.1)), "no room to move: {}", case);
+                    }
+
+                    let floor = (circus[0] as usize - 1) * (circus[1] as usize - 1) - walls.len();
+                    assert_eq!(
+                        reachable(circus, &walls, head),
+                        floor,
+                        "unreachable: {}",
+                        case
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_level() {
+        for &generator in Generator::ALL.iter() {
+            let cells = |seed| -> Vec<(i32, i32)> {
+                generator
+                    .generate(&[40, 30], &mut Pcg64Mcg::seed_from_u64(seed))
+                    .iter()
+                    .flat_map(|wall| wall.bricks().iter().map(|brick| brick.cell()))
+                    .collect()
+            };
+            assert_eq!(cells(9), cells(9), "{:?}", generator);
+        }
+    }
+}
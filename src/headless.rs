@@ -0,0 +1,150 @@
+use crate::{
+    consts,
+    event::GameEvent,
+    format::{self, Versioned},
+    game::Game,
+    obstacle::{DynamicWall, Portal},
+    powerup::{Effects, PowerUp},
+    Direction, Fruit, GameMode, GameStatus, Snake, Wall,
+};
+use serde::Serialize;
+use std::io::{self, BufRead, Write};
+
+/// 每一步写给机器人的局面和这一步中发生的事件
+/// 不包括随机数生成器的状态，否则机器人可以预测之后所有食物的位置
+#[derive(Serialize)]
+pub struct State<'a> {
+    pub mode: GameMode,                   // 游戏模式
+    pub circus: [u32; 2],                 // 移动空间
+    pub board_wall: &'a Wall,             // 边框
+    pub walls: &'a [Wall],                // 墙壁
+    pub dynamic_walls: &'a [DynamicWall], // 会随时间变化的墙壁
+    pub portals: &'a [Portal],            // 传送门
+    pub snake: &'a Snake,                 // 蛇蛇
+    pub fruit: &'a Fruit,                 // 食物
+    pub power_up: Option<&'a PowerUp>,    // 场上的道具
+    pub effects: &'a Effects,             // 生效中的道具效果
+    pub score: u32,                       // 分数
+    pub ticks: u64,                       // 经过的步数
+    pub elapsed: f64,                     // 已经进行的时间
+    pub status: &'a GameStatus,           // 进行中或已经结束
+    pub events: &'a [GameEvent],          // 上一次输出之后发生的事件
+}
+
+impl<'a> State<'a> {
+    /// 本局当前的局面，以及上一次输出之后发生的事件
+    pub fn new(game: &'a Game, events: &'a [GameEvent]) -> Self {
+        State {
+            mode: game.mode,
+            circus: game.circus,
+            board_wall: &game.board_wall,
+            walls: &game.walls,
+            dynamic_walls: &game.dynamic_walls,
+            portals: &game.portals,
+            snake: &game.snake,
+            fruit: &game.fruit,
+            power_up: game.power_up.as_ref(),
+            effects: &game.effects,
+            score: game.score,
+            ticks: game.ticks,
+            elapsed: game.elapsed,
+            status: &game.status,
+            events,
+        }
+    }
+}
+
+/// 无界面运行一局游戏，供服务器上的机器人使用
+/// 每一步之前从标准输入读取一行指令：`up`、`down`、`left`、`right`转向，空行保持方向，`quit`结束
+/// 开始时和每一步之后把带版本号的局面以一行JSON写到标准输出，本局结束后退出
+/// 每一步按蛇当前的速度计时，限时和生存模式的时间与窗口中一致
+pub fn run(seed: u64, mode: GameMode) -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    let mut game = Game::new(mode, consts::HEADLESS_SIZE, seed);
+
+    write_state(&mut output, &mut game)?;
+    for line in stdin.lock().lines() {
+        let direction = match line?.trim() {
+            "up" => Some(Direction::Up),
+            "down" => Some(Direction::Down),
+            "left" => Some(Direction::Left),
+            "right" => Some(Direction::Right),
+            "" => None,
+            "quit" => break,
+            command => {
+                eprintln!("Unknown command: {}", command);
+                continue;
+            }
+        };
+        if let Some(direction) = direction {
+            game.turn(direction);
+        }
+
+        game.update(1.0 / game.velocity());
+        write_state(&mut output, &mut game)?;
+        if game.status == GameStatus::GAMEOVER {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+// 取出本局排队的事件，和局面一起写成一行JSON
+fn write_state<W: Write>(output: &mut W, game: &mut Game) -> io::Result<()> {
+    let events = game.take_events();
+    let state = Versioned {
+        version: format::VERSION,
+        data: State::new(game, &events),
+    };
+    serde_json::to_writer(&mut *output, &state)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    writeln!(output)?;
+
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_hides_the_rng() {
+        let mut game = Game::new(GameMode::Classic, consts::HEADLESS_SIZE, 7);
+        let mut output = Vec::new();
+        write_state(&mut output, &mut game).unwrap();
+
+        let line: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let data = &line["data"];
+        assert_eq!(line["version"], format::VERSION);
+        assert!(data.get("rng").is_none());
+        assert_eq!(data["score"], 0);
+        assert!(data.get("snake").is_some());
+    }
+
+    #[test]
+    fn state_carries_the_events_since_the_last_line() {
+        let mut game = Game::new(GameMode::Classic, consts::HEADLESS_SIZE, 7);
+        let mut output = Vec::new();
+        write_state(&mut output, &mut game).unwrap();
+        game.update(1.0 / game.velocity());
+        write_state(&mut output, &mut game).unwrap();
+
+        let lines: Vec<serde_json::Value> = output
+            .split(|&byte| byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        let events = |line: &serde_json::Value| line["data"]["events"].as_array().unwrap().clone();
+        assert!(events(&lines[0])[0].get("Started").is_some());
+        assert!(events(&lines[1])
+            .iter()
+            .any(|event| event.get("Moved").is_some()));
+        assert!(events(&lines[1])
+            .iter()
+            .all(|event| event.get("Started").is_none()));
+        assert_eq!(lines[1]["data"]["ticks"], 1);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{
+    app::App,
+    consts,
+    locale::{tr, trf},
+    GameMode,
+};
+use serde::{Deserialize, Serialize};
+
+/// HUD中可以显示的信息
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum HudItem {
+    Score,     // 分数
+    HighScore, // 最高分
+    Length,    // 蛇的长度
+    Speed,     // 当前速度
+    Time,      // 本局用时
+    Fruits,    // 吃到的食物数量
+    Level,     // 等级
+    NextFruit, // 下一个食物的种类
+    PowerUps,  // 生效中的道具和剩余时间
+    Mode,      // 游戏模式和它的结束条件
+}
+
+/// 开启鼠标操作时HUD中的屏幕按钮，排在HUD项后面
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HudButton {
+    Pause,   // 暂停并打开暂停菜单
+    Restart, // 重新开始本局
+}
+
+impl HudButton {
+    /// 所有屏幕按钮
+    pub const ALL: [HudButton; 2] = [HudButton::Pause, HudButton::Restart];
+
+    /// 按钮上的文字
+    pub fn name(&self) -> &'static str {
+        match self {
+            HudButton::Pause => "Pause",
+            HudButton::Restart => "Restart",
+        }
+    }
+}
+
+impl HudItem {
+    /// 所有HUD项，也是它们在HUD中的排列顺序
+    pub const ALL: [HudItem; 10] = [
+        HudItem::Score,
+        HudItem::HighScore,
+        HudItem::Length,
+        HudItem::Speed,
+        HudItem::Time,
+        HudItem::Fruits,
+        HudItem::Level,
+        HudItem::NextFruit,
+        HudItem::PowerUps,
+        HudItem::Mode,
+    ];
+
+    /// HUD项名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            HudItem::Score => "Score",
+            HudItem::HighScore => "High score",
+            HudItem::Length => "Length",
+            HudItem::Speed => "Speed",
+            HudItem::Time => "Time",
+            HudItem::Fruits => "Fruits",
+            HudItem::Level => "Level",
+            HudItem::NextFruit => "Next",
+            HudItem::PowerUps => "Power-ups",
+            HudItem::Mode => "Mode",
+        }
+    }
+
+    /// HUD项按界面语言显示的文字
+    pub fn text(&self, app: &App) -> String {
+        match self {
+            HudItem::Score => trf("Score {}", &[&app.game.score]),
+            HudItem::HighScore => {
+                let best = app.high_scores.get(app.mode).best();
+                match app.mode.ranks_by_time() {
+                    true => trf("Best {}", &[&app.mode.format_result(best)]),
+                    false => trf("Best {}", &[&best.max(app.game.score)]),
+                }
+            }
+            HudItem::Length => trf("Length {}", &[&app.game.snake.length()]),
+            HudItem::Speed => trf("Speed {}", &[&format!("{:.1}", app.game.velocity())]),
+            HudItem::Time => trf("Time {}", &[&format_time(app.game.elapsed)]),
+            HudItem::Fruits => trf("Fruits {}", &[&app.game.fruits]),
+            HudItem::Level => trf(
+                "Level {}",
+                &[&(app.game.fruits / consts::FRUITS_PER_LEVEL + 1)],
+            ),
+            HudItem::NextFruit => tr("Next").to_string(),
+            HudItem::PowerUps => app
+                .game
+                .effects
+                .active()
+                .iter()
+                .map(|effect| {
+                    let seconds = effect.remaining as f64 / app.game.velocity();
+                    trf("{} {}s", &[&tr(effect.kind.name()), &seconds.ceil()])
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+            HudItem::Mode => match app.mode {
+                GameMode::TimeAttack => {
+                    let left = (consts::TIME_ATTACK_SECONDS - app.game.elapsed).ceil();
+                    trf("Left {}", &[&format_time(left)])
+                }
+                GameMode::Survival => trf(
+                    "Walls in {}s",
+                    &[&(app.game.next_wall - app.game.elapsed).ceil()],
+                ),
+                GameMode::Target => trf(
+                    "Length {}/{}",
+                    &[&app.game.snake.length(), &consts::TARGET_LENGTH],
+                ),
+                mode => tr(mode.name()).to_string(),
+            },
+        }
+    }
+
+    /// HUD项文字旁边的色块
+    pub fn swatch(&self, app: &App) -> Option<[f32; 4]> {
+        match self {
+            HudItem::NextFruit => Some(app.theme.fruit_color(app.game.fruits as usize + 1)),
+            _ => None,
+        }
+    }
+}
+
+/// 把文字截断到`max_width`以内，截断的文字末尾加上省略号，`width`用来测量文字的宽度
+pub fn fit<F: FnMut(&str) -> f64>(text: &str, max_width: f64, mut width: F) -> String {
+    if width(text) <= max_width {
+        return text.to_string();
+    }
+
+    let mut chars: Vec<char> = text.chars().collect();
+    while chars.pop().is_some() {
+        let fitted = format!("{}...", chars.iter().collect::<String>().trim_end());
+        if width(&fitted) <= max_width {
+            return fitted;
+        }
+    }
+
+    String::new()
+}
+
+/// 把秒数格式化为`分:秒`
+pub fn format_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每个字符宽10
+    fn width(text: &str) -> f64 {
+        text.chars().count() as f64 * 10.0
+    }
+
+    #[test]
+    fn short_text_is_kept() {
+        assert_eq!(fit("Score 12", 112.0, width), "Score 12");
+        assert_eq!(fit("", 0.0, width), "");
+    }
+
+    #[test]
+    fn long_text_is_cut_with_an_ellipsis() {
+        let fitted = fit("Slow 3s Ghost 4s Magnet 9s", 112.0, width);
+
+        assert_eq!(fitted, "Slow 3s...");
+        assert!(width(&fitted) <= 112.0);
+        assert_eq!(fit("减速3秒 穿身4秒", 60.0, width), "减速3...");
+        assert_eq!(fit("Magnet", 20.0, width), "");
+    }
+
+    #[test]
+    fn times_are_shown_as_minutes_and_seconds() {
+        assert_eq!(format_time(0.0), "0:00");
+        assert_eq!(format_time(65.9), "1:05");
+        assert_eq!(format_time(-3.0), "0:00");
+    }
+}
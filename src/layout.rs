@@ -0,0 +1,173 @@
+use crate::consts;
+use piston::RenderArgs;
+
+/// 窗口中各区域的布局
+/// 移动空间按正方形格子等比缩放并居中，顶部留出HUD区域
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub window: [f64; 2],   // 窗口的逻辑尺寸
+    pub hud: [f64; 4],      // HUD区域
+    pub hud_columns: usize, // HUD每行显示的项数
+    pub board: [f64; 4],    // 移动空间，包括边框
+    pub cell: f64,          // 格子的边长
+    pub scale: f64,         // 物理像素与逻辑尺寸的比例，HiDPI屏幕上大于1
+}
+
+impl Layout {
+    /// 按窗口尺寸计算布局
+    /// `circus`是移动空间的尺寸，边框占据坐标为0和`circus`的格子
+    /// HUD按`hud_items`项的数量分成若干行，保证不与移动空间重叠
+    pub fn new(circus: &[u32; 2], window: [f64; 2], scale: f64, hud_items: usize) -> Self {
+        let columns = (circus[0] + 1) as f64;
+        let rows = (circus[1] + 1) as f64;
+
+        let hud_columns = ((window[0] / consts::HUD_ITEM_WIDTH).floor() as usize).max(1);
+        let hud_rows = hud_items.div_ceil(hud_columns);
+        let hud_height = match hud_rows {
+            0 => 0.0,
+            _ => hud_rows as f64 * consts::HUD_LINE_HEIGHT + consts::HUD_PADDING,
+        }
+        .min(window[1]);
+        let available = [window[0], (window[1] - hud_height).max(0.0)];
+
+        // 格子边长对齐到物理像素，避免格子之间出现缝隙
+        let cell = (available[0] / columns).min(available[1] / rows);
+        let cell = match (cell * scale).floor() {
+            pixels if pixels >= 1.0 => pixels / scale,
+            _ => cell,
+        };
+
+        let width = cell * columns;
+        let height = cell * rows;
+        let x = ((window[0] - width) * 0.5 * scale).floor() / scale;
+        let y = hud_height + ((available[1] - height) * 0.5 * scale).floor() / scale;
+
+        Layout {
+            window,
+            hud: [0.0, 0.0, window[0], hud_height],
+            hud_columns,
+            board: [x, y, width, height],
+            cell,
+            scale,
+        }
+    }
+
+    /// 按渲染参数计算布局
+    pub fn from_args(circus: &[u32; 2], args: &RenderArgs, hud_items: usize) -> Self {
+        let scale = match args.window_size[0] > 0.0 {
+            true => args.draw_size[0] as f64 / args.window_size[0],
+            false => 1.0,
+        };
+
+        Layout::new(circus, args.window_size, scale, hud_items)
+    }
+
+    /// 第`index`个HUD项文字的起点
+    pub fn hud_item_pos(&self, index: usize) -> [f64; 2] {
+        let column = index % self.hud_columns;
+        let row = index / self.hud_columns;
+
+        [
+            self.hud[0] + consts::HUD_PADDING + column as f64 * consts::HUD_ITEM_WIDTH,
+            self.hud[1] + (row + 1) as f64 * consts::HUD_LINE_HEIGHT,
+        ]
+    }
+
+    /// 第`index`个HUD项所占的矩形区域，屏幕按钮排在HUD项后面，也使用这个区域
+    pub fn hud_item_rect(&self, index: usize) -> [f64; 4] {
+        let pos = self.hud_item_pos(index);
+
+        [
+            pos[0] - consts::HUD_PADDING * 0.5,
+            pos[1] - consts::HUD_LINE_HEIGHT + 2.0,
+            consts::HUD_ITEM_WIDTH - consts::HUD_PADDING,
+            consts::HUD_LINE_HEIGHT,
+        ]
+    }
+
+    /// 坐标为`(x, y)`的格子在窗口中的矩形区域
+    pub fn cell_rect(&self, x: i32, y: i32) -> [f64; 4] {
+        [
+            self.board[0] + (x as f64) * self.cell,
+            self.board[1] + (y as f64) * self.cell,
+            self.cell,
+            self.cell,
+        ]
+    }
+
+    /// 窗口中的位置所在的格子，不在移动空间内时返回None
+    pub fn cell_at(&self, pos: &[f64; 2]) -> Option<(i32, i32)> {
+        if self.cell <= 0.0 {
+            return None;
+        }
+
+        let x = ((pos[0] - self.board[0]) / self.cell).floor();
+        let y = ((pos[1] - self.board[1]) / self.cell).floor();
+        let columns = (self.board[2] / self.cell).round();
+        let rows = (self.board[3] / self.cell).round();
+
+        match x >= 0.0 && y >= 0.0 && x < columns && y < rows {
+            true => Some((x as i32, y as i32)),
+            false => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 格子中心在窗口中的位置
+    fn center(layout: &Layout, x: i32, y: i32) -> [f64; 2] {
+        let rect = layout.cell_rect(x, y);
+        [rect[0] + rect[2] * 0.5, rect[1] + rect[3] * 0.5]
+    }
+
+    #[test]
+    fn board_is_letterboxed_into_whole_pixel_cells() {
+        let layout = Layout::new(&[40, 30], [640.0, 480.0], 1.0, 0);
+
+        assert_eq!(layout.cell, 15.0);
+        assert_eq!(layout.board, [12.0, 7.0, 615.0, 465.0]);
+        assert_eq!(layout.cell_rect(0, 0), [12.0, 7.0, 15.0, 15.0]);
+        assert_eq!(layout.cell_rect(3, 2), [57.0, 37.0, 15.0, 15.0]);
+    }
+
+    #[test]
+    fn hud_never_overlaps_the_board() {
+        let layout = Layout::new(&[40, 30], [640.0, 480.0], 1.0, 12);
+
+        assert_eq!(layout.hud_columns, 5);
+        assert_eq!(
+            layout.hud[3],
+            3.0 * consts::HUD_LINE_HEIGHT + consts::HUD_PADDING
+        );
+        assert!(layout.board[1] >= layout.hud[3]);
+        assert!(layout.board[1] + layout.board[3] <= 480.0);
+    }
+
+    #[test]
+    fn cells_map_back_after_a_resize() {
+        for window in [[640.0, 480.0], [1280.0, 960.0], [500.0, 900.0]].iter() {
+            for &scale in [1.0, 2.0].iter() {
+                let layout = Layout::new(&[40, 30], *window, scale, 6);
+
+                for &(x, y) in [(0, 0), (10, 10), (40, 30), (39, 1)].iter() {
+                    assert_eq!(layout.cell_at(&center(&layout, x, y)), Some((x, y)));
+                }
+                assert_eq!(
+                    layout.cell_at(&[layout.board[0] - 1.0, layout.board[1]]),
+                    None
+                );
+                assert_eq!(layout.cell_at(&center(&layout, 41, 5)), None);
+            }
+        }
+    }
+
+    #[test]
+    fn tiny_windows_do_not_break_the_mapping() {
+        let layout = Layout::new(&[40, 30], [0.0, 0.0], 1.0, 3);
+
+        assert_eq!(layout.cell_at(&[0.0, 0.0]), None);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{
+    map::LevelMap,
+    obstacle::{Behavior, DynamicWall, Portal},
+    settings::Settings,
+    Wall,
+};
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 内置的传送门关卡
+const PORTALS_LEVEL: &str = include_str!("../assets/levels/portals.toml");
+
+/// 可选择的关卡
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Level {
+    Random,    // 随机生成的墙壁
+    Open,      // 只有边框的空旷场地
+    Sliding,   // 来回滑动的墙壁
+    Rising,    // 随着吃到的食物逐渐出现的墙壁
+    Blinking,  // 时有时无的砖块
+    Portals,   // 成对的传送门
+    Generated, // 按设置中的生成器和种子生成
+    Custom,    // 在关卡编辑器中编辑的关卡，不在关卡选择菜单中列出
+}
+
+impl Level {
+    /// 关卡选择菜单中列出的全部关卡
+    pub const ALL: [Level; 7] = [
+        Level::Random,
+        Level::Open,
+        Level::Sliding,
+        Level::Rising,
+        Level::Blinking,
+        Level::Portals,
+        Level::Generated,
+    ];
+
+    /// 关卡名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Random => "Random walls",
+            Level::Open => "Open field",
+            Level::Sliding => "Sliding walls",
+            Level::Rising => "Rising walls",
+            Level::Blinking => "Blinking bricks",
+            Level::Portals => "Portals",
+            Level::Generated => "Generated",
+            Level::Custom => "Custom",
+        }
+    }
+
+    /// 用本局的`rng`生成关卡中的墙壁，同样种子的`rng`总是生成同样的墙壁
+    /// 随机墙壁按设置中的密度生成，生成的关卡使用设置中的生成器
+    pub fn walls(&self, circus: &[u32; 2], settings: &Settings, rng: &mut Pcg64Mcg) -> Vec<Wall> {
+        let mut walls = Vec::<Wall>::new();
+
+        match self {
+            Level::Random => {
+                let (low, high) = settings.wall_density.range();
+                let walls_num = match low < high {
+                    true => rng.gen_range(low, high),
+                    false => low,
+                };
+                for _ in 0..walls_num {
+                    let brick_num = rng.gen_range(5, 10);
+
+                    walls.push(Wall::randnew(Some(brick_num), circus, rng));
+                }
+            }
+            Level::Portals => walls = Level::map(PORTALS_LEVEL).walls(circus),
+            Level::Generated => walls = settings.generator.generate(circus, rng),
+            _ => (),
+        }
+
+        walls
+    }
+
+    // 解析内置的关卡文件
+    fn map(content: &str) -> LevelMap {
+        LevelMap::parse(content).expect("Error parsing built-in level")
+    }
+
+    /// 关卡中会随时间变化的墙壁
+    /// 位置按移动空间的比例计算，避开蛇出生的那一行
+    pub fn dynamic_walls(&self, circus: &[u32; 2]) -> Vec<DynamicWall> {
+        let width = circus[0] as i32;
+        let height = circus[1] as i32;
+
+        match self {
+            Level::Sliding => {
+                let len = (width / 4).max(1);
+                let distance = (width - 3 - len).max(0) as u32;
+                vec![
+                    DynamicWall::new(
+                        Wall::from_cells(&line((1, height / 3), (1, 0), len)),
+                        Behavior::Sliding {
+                            step: (1, 0),
+                            distance,
+                            interval: 2,
+                        },
+                    ),
+                    DynamicWall::new(
+                        Wall::from_cells(&line((width - 1 - len, height * 2 / 3), (1, 0), len)),
+                        Behavior::Sliding {
+                            step: (-1, 0),
+                            distance,
+                            interval: 2,
+                        },
+                    ),
+                ]
+            }
+            Level::Rising => {
+                let len = (height / 2).max(1);
+                vec![
+                    DynamicWall::new(
+                        Wall::from_cells(&line((width / 4, height / 4), (0, 1), len)),
+                        Behavior::Appearing { fruits: 3 },
+                    ),
+                    DynamicWall::new(
+                        Wall::from_cells(&line((width * 3 / 4, height / 4), (0, 1), len)),
+                        Behavior::Appearing { fruits: 6 },
+                    ),
+                    DynamicWall::new(
+                        Wall::from_cells(&line((width / 3, height / 4), (1, 0), width / 3)),
+                        Behavior::Appearing { fruits: 9 },
+                    ),
+                    DynamicWall::new(
+                        Wall::from_cells(&line((width / 3, height * 3 / 4), (1, 0), width / 3)),
+                        Behavior::Appearing { fruits: 12 },
+                    ),
+                ]
+            }
+            Level::Blinking => (1..5)
+                .map(|i| {
+                    // 上下交错排列，不经过蛇出生的那一行
+                    let len = (height / 2 - 2 - height / 8).max(1);
+                    let start = match i % 2 {
+                        0 => (width * i / 5, height / 2 - 1 - len),
+                        _ => (width * i / 5, height / 2 + 2),
+                    };
+                    DynamicWall::new(
+                        Wall::from_cells(&line(start, (0, 1), len)),
+                        Behavior::Blinking {
+                            period: 10,
+                            offset: (i as u32 % 2) * 10,
+                        },
+                    )
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// 关卡中的传送门，包括关卡文件中固定位置的和随机生成的
+    /// 随机生成的传送门用本局的`rng`选择位置，避开`walls`和其他传送门
+    pub fn portals(&self, circus: &[u32; 2], walls: &[Wall], rng: &mut Pcg64Mcg) -> Vec<Portal> {
+        let map = match self {
+            Level::Portals => Level::map(PORTALS_LEVEL),
+            _ => return Vec::new(),
+        };
+
+        let mut portals = map.portals(circus);
+        let mut taken: HashSet<(i32, i32)> = walls
+            .iter()
+            .flat_map(|wall| wall.bricks.iter())
+            .map(|brick| (brick.pos_x, brick.pos_y))
+            .chain(portals.iter().flat_map(|portal| portal.cells().to_vec()))
+            .collect();
+
+        for _ in 0..map.random_portals {
+            if let Some(portal) = Portal::randnew(circus, &taken, rng) {
+                taken.extend(portal.cells().iter());
+                portals.push(portal);
+            }
+        }
+
+        portals
+    }
+}
+
+/// 从`start`开始沿`step`方向的`len`个格子
+fn line(start: (i32, i32), step: (i32, i32), len: i32) -> Vec<(i32, i32)> {
+    (0..len)
+        .map(|i| (start.0 + step.0 * i, start.1 + step.1 * i))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    // 用`seed`生成关卡，依次返回所有砖块和传送门两端所在的格子
+    fn layout(level: Level, seed: u64) -> Vec<(i32, i32)> {
+        let circus = [40, 30];
+        let settings = Settings {
+            generator: crate::generator::Generator::Caves,
+            ..Settings::default()
+        };
+        let mut rng = Pcg64Mcg::seed_from_u64(seed);
+        let walls = level.walls(&circus, &settings, &mut rng);
+        let portals = level.portals(&circus, &walls, &mut rng);
+
+        walls
+            .iter()
+            .flat_map(|wall| wall.bricks.iter())
+            .map(|brick| (brick.pos_x, brick.pos_y))
+            .chain(portals.iter().flat_map(|portal| portal.cells().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn the_seed_reproduces_every_level() {
+        for &level in Level::ALL.iter() {
+            assert_eq!(layout(level, 5), layout(level, 5), "{:?}", level);
+        }
+
+        // 随机墙壁和随机传送门也由种子决定
+        assert_ne!(layout(Level::Random, 5), layout(Level::Random, 6));
+        assert_ne!(layout(Level::Portals, 5), layout(Level::Portals, 6));
+    }
+}
@@ -0,0 +1,588 @@
+//! 贪吃蛇游戏
+//!
+//! 库中包含游戏的模型：[`Block`]、[`Snake`]、[`Wall`]、[`Fruit`]、碰撞检测[`Collision`]
+//! 和游戏状态[`GameStatus`]，以及关卡、道具、存档等游戏逻辑。
+//! 开启`gl`特性时，基于Piston的窗口程序位于`app`模块，可执行文件只是它的一层薄包装；
+//! 终端界面和无界面运行分别位于`tui`和`headless`模块。
+
+pub use collision::{Collision, Collited};
+pub use error::Error;
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::min;
+#[cfg(feature = "gl")]
+use theme::Theme;
+
+/// 应用程序主体
+#[cfg(feature = "gl")]
+pub mod app;
+/// 资源文件
+#[cfg(feature = "gl")]
+pub mod assets;
+/// 声音系统
+#[cfg(feature = "gl")]
+pub mod audio;
+/// 碰撞系统
+pub mod collision;
+/// 包含会用到的预设常量
+pub mod consts;
+/// 关卡编辑器
+#[cfg(feature = "gl")]
+pub mod editor;
+/// 错误类型
+pub mod error;
+/// 游戏事件
+pub mod event;
+/// 带版本号的JSON和二进制格式
+#[cfg(feature = "serde")]
+pub mod format;
+/// 没有窗口的经典模式游戏
+pub mod game;
+/// 手柄输入
+#[cfg(feature = "gl")]
+pub mod gamepad;
+/// 程序化关卡生成器
+pub mod generator;
+/// 无界面运行
+#[cfg(feature = "headless")]
+pub mod headless;
+/// HUD
+#[cfg(feature = "gl")]
+pub mod hud;
+/// 窗口布局
+#[cfg(feature = "gl")]
+pub mod layout;
+/// 关卡
+#[cfg(feature = "gl")]
+pub mod level;
+/// 界面语言和消息目录
+#[cfg(feature = "gl")]
+pub mod locale;
+/// 关卡文件
+#[cfg(feature = "serde")]
+pub mod map;
+/// 菜单系统
+#[cfg(feature = "gl")]
+pub mod menu;
+/// 动态墙壁和传送门
+pub mod obstacle;
+/// 道具
+pub mod powerup;
+/// 渲染系统
+#[cfg(feature = "gl")]
+pub mod render;
+/// 录像
+#[cfg(feature = "serde")]
+pub mod replay;
+/// 存档
+#[cfg(feature = "gl")]
+pub mod save;
+/// 高分榜
+pub mod score;
+/// 玩家设置
+#[cfg(feature = "gl")]
+pub mod settings;
+/// 统计数据
+pub mod stats;
+/// 配色主题
+#[cfg(feature = "gl")]
+pub mod theme;
+/// 图块集
+#[cfg(feature = "gl")]
+pub mod tileset;
+/// 终端界面
+#[cfg(feature = "tui")]
+pub mod tui;
+
+/// 蛇的移动方向枚举
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    /// 相反的方向，蛇不能直接掉头
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+/// 基础的游戏实体
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Block {
+    /// x坐标
+    pos_x: i32,
+    /// y坐标
+    pos_y: i32,
+    /// 被碰撞时返回的碰撞检测类型
+    collited: Collited,
+    /// 实体的颜色
+    color: [f32; 4],
+}
+
+impl Block {
+    /// 创建新的Block实例
+    pub fn new(_pos_x: i32, _pos_y: i32, _collited: Collited, _color: [f32; 4]) -> Self {
+        Block {
+            pos_x: _pos_x,
+            pos_y: _pos_y,
+            collited: _collited,
+            color: _color,
+        }
+    }
+
+    /// 所在的格子
+    pub fn cell(&self) -> (i32, i32) {
+        (self.pos_x, self.pos_y)
+    }
+
+    /// 被碰撞时返回的碰撞检测类型
+    pub fn collited(&self) -> &Collited {
+        &self.collited
+    }
+
+    /// 实体的颜色
+    pub fn color(&self) -> [f32; 4] {
+        self.color
+    }
+}
+
+/// 墙壁实体
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Wall {
+    bricks: Vec<Block>,
+}
+
+impl Wall {
+    /// 边框
+    /// `circus: &[u32; 2]` 是玩家的可移动范围
+    pub fn board_wall(circus: &[u32; 2]) -> Self {
+        let width = circus[0] as i32;
+        let height = circus[1] as i32;
+
+        let wall_color = consts::LIGHTBLUE;
+        let mut bricks = Vec::<Block>::new();
+
+        // 添加上下俩个边框
+        for i in 0..width {
+            let brick_up = Block::new(i, height, Collited::WithWall, wall_color);
+            let brick_down = Block::new(i, 0, Collited::WithWall, wall_color);
+
+            bricks.push(brick_up);
+            bricks.push(brick_down);
+        }
+
+        // 添加左右俩个边框
+        for i in 0..height {
+            let brick_left = Block::new(0, i, Collited::WithWall, wall_color);
+            let brick_right = Block::new(width, i, Collited::WithWall, wall_color);
+
+            bricks.push(brick_left);
+            bricks.push(brick_right);
+        }
+
+        Wall { bricks }
+    }
+
+    /// 用`rng`创建一堵随机的墙
+    pub fn randnew<R: Rng>(brick_num: Option<u32>, circus: &[u32; 2], rng: &mut R) -> Self {
+        let x = rng.gen_range(1, (circus[0] - 1) as i32);
+        let y = rng.gen_range(1, (circus[1] - 1) as i32);
+
+        let mut bricks = Vec::<Block>::new();
+
+        let n = match brick_num {
+            Some(num) => num,
+            None => rng.gen_range(1, min(circus[0], circus[1]) / 2),
+        };
+
+        for i in 0..n {
+            let d = match rng.gen_bool(0.5) {
+                true => 1i32,
+                false => -1i32,
+            };
+            let (dx, dy) = match rng.gen_bool(0.5) {
+                true => (d, 0),
+                false => (0, d),
+            };
+
+            let x_pre = match bricks.get(i as usize) {
+                Some(brick) => brick.pos_x,
+                None => x,
+            };
+
+            let y_pre = match bricks.get(i as usize) {
+                Some(brick) => brick.pos_y,
+                None => y,
+            };
+
+            let brick = Block::new(
+                x_pre + dx * (i as i32),
+                y_pre + dy * (i as i32),
+                Collited::WithWall,
+                consts::LIGHTBLUE,
+            );
+
+            bricks.push(brick);
+        }
+
+        //println!("{:?}/n", bricks);
+
+        Wall { bricks }
+    }
+
+    /// 组成墙壁的砖块
+    pub fn bricks(&self) -> &[Block] {
+        &self.bricks
+    }
+
+    /// 修改墙壁的颜色
+    pub fn paint(&mut self, color: [f32; 4]) {
+        for brick in self.bricks.iter_mut() {
+            brick.color = color;
+        }
+    }
+
+    /// 由指定格子上的砖块组成的墙壁
+    pub fn from_cells(cells: &[(i32, i32)]) -> Self {
+        let bricks = cells
+            .iter()
+            .map(|&(x, y)| Block::new(x, y, Collited::WithWall, consts::LIGHTBLUE))
+            .collect();
+
+        Wall { bricks }
+    }
+
+    /// 整体平移墙壁
+    pub fn shift(&mut self, dx: i32, dy: i32) {
+        for brick in self.bricks.iter_mut() {
+            brick.pos_x += dx;
+            brick.pos_y += dy;
+        }
+    }
+
+    /// 摧毁位于`(x, y)`的砖块，返回是否有砖块被摧毁
+    pub fn remove_brick(&mut self, x: i32, y: i32) -> bool {
+        let len = self.bricks.len();
+        self.bricks.retain(|brick| brick.pos_x != x || brick.pos_y != y);
+
+        self.bricks.len() != len
+    }
+}
+
+/// 食物实体
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Fruit {
+    block: Block,
+}
+
+impl Fruit {
+    /// 在`(x, y)`创建Fruit实例
+    pub fn at(x: i32, y: i32) -> Self {
+        Fruit {
+            block: Block::new(x, y, Collited::WithFruit, consts::GREEN),
+        }
+    }
+
+    /// 用`rng`创建位置随机的Fruit实例
+    pub fn randnew<R: Rng>(
+        horizontal_block_num: u32,
+        vertical_block_num: u32,
+        rng: &mut R,
+    ) -> Self {
+        Fruit {
+            block: Block {
+                pos_x: rng.gen_range(1, (horizontal_block_num - 1) as i32),
+                pos_y: rng.gen_range(1, (vertical_block_num - 1) as i32),
+                collited: Collited::WithFruit,
+                color: consts::GREEN,
+            },
+        }
+    }
+
+    /// 食物所在的块
+    pub fn block(&self) -> &Block {
+        &self.block
+    }
+
+    /// 修改食物的颜色
+    pub fn paint(&mut self, color: [f32; 4]) {
+        self.block.color = color;
+    }
+}
+
+/// 蛇的实体
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Snake {
+    head: Block,
+    body: Vec<Block>,
+    body_color: [f32; 4],
+    velocity: f64,
+    direction: Direction,
+    direction_lock: bool,
+    growth_flag: bool,
+}
+
+impl Snake {
+    /// 在移动范围中央创建新的蛇蛇
+    pub fn new(horizontal_block_num: u32, vertical_block_num: u32) -> Self {
+        let center_x = ((horizontal_block_num as f64) * 0.5) as i32;
+        let center_y = ((vertical_block_num as f64) * 0.5) as i32;
+
+        Snake {
+            head: Block {
+                pos_x: center_x,
+                pos_y: center_y,
+                collited: Collited::WithSnake,
+                color: consts::RED,
+            },
+            body: vec![
+                Block {
+                    pos_x: center_x + 1,
+                    pos_y: center_y,
+                    collited: Collited::WithSnake,
+                    color: consts::WHITE,
+                },
+                Block {
+                    pos_x: center_x + 2,
+                    pos_y: center_y,
+                    collited: Collited::WithSnake,
+                    color: consts::WHITE,
+                },
+                Block {
+                    pos_x: center_x + 3,
+                    pos_y: center_y,
+                    collited: Collited::WithSnake,
+                    color: consts::WHITE,
+                },
+                Block {
+                    pos_x: center_x + 4,
+                    pos_y: center_y,
+                    collited: Collited::WithSnake,
+                    color: consts::WHITE,
+                },
+            ],
+            body_color: consts::WHITE,
+            velocity: 6.0,
+            direction: Direction::Left,
+            direction_lock: false,
+            growth_flag: false,
+        }
+    }
+
+    /// 蛇头位于`(x, y)`、朝着`direction`的新蛇，蛇身向反方向延伸
+    pub fn at(x: i32, y: i32, direction: Direction) -> Self {
+        let (dx, dy) = match direction {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (1, 0),
+            Direction::Right => (-1, 0),
+        };
+
+        Snake {
+            head: Block::new(x, y, Collited::WithSnake, consts::RED),
+            body: (1..5)
+                .map(|i| Block::new(x + dx * i, y + dy * i, Collited::WithSnake, consts::WHITE))
+                .collect(),
+            body_color: consts::WHITE,
+            velocity: 6.0,
+            direction,
+            direction_lock: false,
+            growth_flag: false,
+        }
+    }
+
+    /// 按主题给蛇上色，蛇身从头到尾渐变
+    #[cfg(feature = "gl")]
+    pub fn paint(&mut self, theme: &Theme) {
+        let len = self.body.len();
+
+        self.head.color = theme.head;
+        self.body_color = theme.body_start;
+        for (i, block) in self.body.iter_mut().enumerate() {
+            block.color = theme.body_color(i, len);
+        }
+    }
+
+    /// 蛇的长度，包括蛇头
+    pub fn length(&self) -> usize {
+        self.body.len() + 1
+    }
+
+    /// 蛇头
+    pub fn head(&self) -> &Block {
+        &self.head
+    }
+
+    /// 蛇身，从紧挨蛇头的一块到蛇尾
+    pub fn body(&self) -> &[Block] {
+        &self.body
+    }
+
+    /// 移动方向
+    pub fn direction(&self) -> &Direction {
+        &self.direction
+    }
+
+    /// 每秒移动的格数
+    pub fn velocity(&self) -> f64 {
+        self.velocity
+    }
+
+    /// 检测蛇是否与自己的身体相碰撞
+    pub fn is_colliting_with_self(&self) -> bool {
+        let mut ans = false;
+        for block in self.body.iter() {
+            match self.head.is_collited_by_block(block) {
+                Collited::NoCollision => continue,
+                _ => {
+                    ans = true;
+                    break;
+                }
+            }
+        }
+
+        ans
+    }
+
+    /// 从位于`(x, y)`的一块开始截断蛇身，返回截掉的块数
+    pub fn trim(&mut self, x: i32, y: i32) -> usize {
+        match self
+            .body
+            .iter()
+            .position(|block| block.pos_x == x && block.pos_y == y)
+        {
+            Some(index) => {
+                let len = self.body.len();
+                self.body.truncate(index);
+                len - index
+            }
+            None => 0,
+        }
+    }
+
+    /// 把蛇头移动到`(x, y)`，蛇身会沿原来的路径跟上
+    pub fn teleport(&mut self, x: i32, y: i32) {
+        self.head.pos_x = x;
+        self.head.pos_y = y;
+    }
+
+    /// 蛇身加长的操作方法，下一次移动时蛇尾不会缩回
+    pub fn growth_action(&mut self) {
+        // 设置 growth_flag
+        self.growth_flag = true;
+        // 增加移动速度
+        self.velocity += 0.01;
+    }
+
+    /// 沿当前方向移动一格
+    pub fn moving(&mut self) {
+        // 坐标移动
+        let (x, y) = match self.direction {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        };
+
+        // 克隆当前的坐标，会成为身体的一部分
+        let mut pre_block = self.head.clone();
+        pre_block.color = self.body_color;
+
+        // 更新蛇头坐标
+        self.head.pos_x += x;
+        self.head.pos_y += y;
+
+        // 通过将蛇体的当前块推到新向量来“移动”蛇
+        let mut blocks = Vec::new();
+        for block in self.body.iter_mut() {
+            blocks.push(pre_block);
+            pre_block = block.clone();
+        }
+
+        // 如果设置了增长标志，请不要浪费任何块。
+        if self.growth_flag {
+            blocks.push(pre_block);
+            self.growth_flag = false;
+        }
+
+        // 分配新的身体
+        self.body = blocks;
+    }
+}
+
+/// 游戏模式
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GameMode {
+    #[default]
+    Classic,    // 无尽的经典模式
+    TimeAttack, // 在限定时间内尽量得分
+    Survival,   // 墙壁随时间不断增加
+    Zen,        // 不会死亡，咬到自己只会截断尾巴
+    Target,     // 尽快达到目标长度
+}
+
+impl GameMode {
+    /// 模式选择菜单中列出的全部模式
+    pub const ALL: [GameMode; 5] = [
+        GameMode::Classic,
+        GameMode::TimeAttack,
+        GameMode::Survival,
+        GameMode::Zen,
+        GameMode::Target,
+    ];
+
+    /// 模式名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::TimeAttack => "Time attack",
+            GameMode::Survival => "Survival",
+            GameMode::Zen => "Zen",
+            GameMode::Target => "Target",
+        }
+    }
+
+    /// 是否按用时排名，用时越短越好，其他模式按分数排名
+    pub fn ranks_by_time(&self) -> bool {
+        *self == GameMode::Target
+    }
+
+    /// 把高分榜中记录的成绩格式化，按用时排名的模式记录的是十分之一秒
+    pub fn format_result(&self, result: u32) -> String {
+        match (self.ranks_by_time(), result) {
+            (true, 0) => "-".to_string(),
+            (true, tenths) => format!("{}.{}s", tenths / 10, tenths % 10),
+            (false, score) => score.to_string(),
+        }
+    }
+}
+
+/// 游戏状态机
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GameStatus {
+    MENU,     // 菜单界面
+    TIMEOUT,  // 游戏暂停
+    RESUMING, // 继续游戏前的倒计时
+    GAMING,   // 游戏进行中
+    GAMEOVER, // 游戏结束
+    RESTART,  // 游戏重启
+    EDITOR,   // 关卡编辑器
+    QUIT,     // 退出程序
+}
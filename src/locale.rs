@@ -0,0 +1,224 @@
+use crate::assets;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+// 读取后的消息目录，第一次翻译时读取
+static CATALOGS: OnceLock<Vec<HashMap<String, String>>> = OnceLock::new();
+// 当前语言在`Language::ALL`中的下标，默认为英语
+static CURRENT: AtomicUsize = AtomicUsize::new(1);
+
+/// 界面语言
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Language {
+    Auto,    // 跟随系统的语言环境
+    English, // 英语
+    Chinese, // 简体中文
+}
+
+impl Language {
+    /// 所有语言
+    pub const ALL: [Language; 3] = [Language::Auto, Language::English, Language::Chinese];
+
+    /// 语言名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Auto => "Auto",
+            Language::English => "English",
+            Language::Chinese => "Chinese",
+        }
+    }
+
+    /// 语言代码，也是资源目录中`locales`下消息目录的文件名
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::Auto => "auto",
+            Language::English => "en",
+            Language::Chinese => "zh-CN",
+        }
+    }
+
+    /// 实际使用的语言，`Auto`按语言环境变量决定
+    pub fn resolve(self) -> Language {
+        match self {
+            Language::Auto => detect(),
+            language => language,
+        }
+    }
+
+    // 内置的消息目录，界面文字的源语言是英语
+    // 消息目录以英语原文为键，没有翻译的消息原样显示
+    fn catalog(&self) -> &'static str {
+        match self {
+            Language::Auto => "",
+            Language::English => include_str!("../assets/locales/en.toml"),
+            Language::Chinese => include_str!("../assets/locales/zh-CN.toml"),
+        }
+    }
+
+    /// 是否需要能显示汉字的字体
+    pub fn needs_cjk_font(&self) -> bool {
+        *self == Language::Chinese
+    }
+
+    // 在`Language::ALL`中的下标
+    fn index(self) -> usize {
+        Language::ALL
+            .iter()
+            .position(|&language| language == self)
+            .unwrap_or(0)
+    }
+}
+
+/// 切换界面语言
+pub fn set(language: Language) {
+    CURRENT.store(language.resolve().index(), Ordering::Relaxed);
+}
+
+/// 当前的界面语言
+pub fn current() -> Language {
+    Language::ALL[CURRENT.load(Ordering::Relaxed)]
+}
+
+/// 翻译一条消息，当前语言的消息目录中没有时显示英语原文
+pub fn tr(message: &'static str) -> &'static str {
+    catalogs()[CURRENT.load(Ordering::Relaxed)]
+        .get(message)
+        .map_or(message, String::as_str)
+}
+
+/// 翻译带参数的消息，译文中的`{}`按顺序替换为参数，`{0}`、`{1}`按编号替换为参数
+/// 按编号替换可以让译文调整参数的顺序
+pub fn trf(message: &'static str, args: &[&dyn Display]) -> String {
+    fill(tr(message), args)
+}
+
+// 把模板中的占位符替换为参数，没有对应参数的占位符原样保留
+fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut result = String::new();
+    let mut rest = template;
+    let mut next = 0;
+
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let index = match &rest[start + 1..end] {
+            "" => {
+                next += 1;
+                Some(next - 1)
+            }
+            number => number.parse::<usize>().ok(),
+        };
+
+        result.push_str(&rest[..start]);
+        match index.and_then(|index| args.get(index)) {
+            Some(arg) => result.push_str(&arg.to_string()),
+            None => result.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+
+    result
+}
+
+// 所有语言的消息目录，按`Language::ALL`的顺序排列
+// 资源目录中`locales`下的同名文件可以补充和修改内置的翻译
+fn catalogs() -> &'static Vec<HashMap<String, String>> {
+    CATALOGS.get_or_init(|| {
+        Language::ALL
+            .iter()
+            .map(|language| {
+                let mut catalog: HashMap<String, String> =
+                    toml::from_str(language.catalog()).expect("Error parsing built-in catalog");
+
+                let name = format!("locales/{}.toml", language.code());
+                if let Some(path) = assets::find(&name) {
+                    match read_catalog(&path) {
+                        Ok(overrides) => catalog.extend(overrides),
+                        Err(e) => eprintln!("Ignoring invalid catalog {}: {}", path.display(), e),
+                    }
+                }
+
+                catalog
+            })
+            .collect()
+    })
+}
+
+// 读取消息目录文件
+fn read_catalog(path: &Path) -> Result<HashMap<String, String>, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    toml::from_str(&content).map_err(|e| e.to_string())
+}
+
+// 从语言环境变量推断界面语言，中文环境使用简体中文，其他环境都使用英语
+fn detect() -> Language {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+
+    match locale.to_lowercase().starts_with("zh") {
+        true => Language::Chinese,
+        false => Language::English,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_filled_in_order() {
+        assert_eq!(fill("{} of {}", &[&1, &"two"]), "1 of two");
+        assert_eq!(fill("no placeholders", &[&1]), "no placeholders");
+    }
+
+    #[test]
+    fn numbered_placeholders_can_reorder_arguments() {
+        let template = &catalogs()[Language::Chinese.index()]["Reached length {} in {}"];
+
+        assert_eq!(fill(template, &[&30, &"1:05"]), "用时1:05达到长度30");
+        assert_eq!(fill("{1}-{0}-{1}", &[&"a", &"b"]), "b-a-b");
+    }
+
+    #[test]
+    fn missing_arguments_keep_the_placeholder() {
+        assert_eq!(fill("{} and {}", &[&1]), "1 and {}");
+        assert_eq!(fill("{2} {x}", &[&1]), "{2} {x}");
+        assert_eq!(fill("open { brace", &[&1]), "open { brace");
+    }
+
+    #[test]
+    fn unknown_messages_are_shown_as_is() {
+        assert_eq!(
+            tr("A message nobody translated"),
+            "A message nobody translated"
+        );
+        assert_eq!(
+            trf("Nobody translated {} either", &[&3]),
+            "Nobody translated 3 either"
+        );
+    }
+
+    #[test]
+    fn translations_keep_every_placeholder() {
+        let count = |text: &str| text.matches('{').count();
+
+        for catalog in catalogs().iter() {
+            for (message, translation) in catalog.iter() {
+                assert_eq!(count(message), count(translation), "{}", message);
+            }
+        }
+    }
+}
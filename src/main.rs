@@ -1,286 +1,99 @@
-use app::App;
-use collision::{Collision, Collited};
-use rand::Rng;
-use std::cmp::min;
-
-/// 应用程序主体
-mod app;
-/// 碰撞系统
-mod collision;
-/// 包含会用到的预设常量
-mod consts;
-/// 渲染系统
-mod render;
-
-fn main() {
-    // 这里的App是主要结构体，构建一个理论尺寸为(80, 60)的结构体
-    let mut app = App::new(80, 60);
-
-    // 运行程序
-    app.run();
-}
-
-/// 蛇的移动方向枚举
-#[derive(PartialEq, Clone)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-/// 基础的游戏实体
-#[derive(Debug, Clone, PartialEq)]
-pub struct Block {
-    /// x坐标
-    pos_x: i32,
-    /// y坐标
-    pos_y: i32,
-    /// 被碰撞时返回的碰撞检测类型
-    collited: Collited,
-    /// 实体的颜色
-    color: [f32; 4],
-}
-
-impl Block {
-    /// 创建新的Block实例
-    pub fn new(_pos_x: i32, _pos_y: i32, _collited: Collited, _color: [f32; 4]) -> Self {
-        Block {
-            pos_x: _pos_x,
-            pos_y: _pos_y,
-            collited: _collited,
-            color: _color,
+use snake::Error;
+#[cfg(any(feature = "tui", feature = "headless"))]
+use snake::GameMode;
+#[cfg(feature = "gl")]
+use snake::{app::App, settings::Settings};
+#[cfg(feature = "gl")]
+use std::path::PathBuf;
+
+// 出错时打印错误信息和解决办法，并以非零状态退出
+fn main() -> Result<(), Error> {
+    let mut args = std::env::args().skip(1);
+    let first = args.next();
+
+    match first.as_deref() {
+        // `--tui [种子] [模式]`在终端中运行
+        #[cfg(feature = "tui")]
+        Some("--tui") => snake::tui::run(seed(args.next()), mode(args.next())).map_err(Error::from),
+        // `--headless [种子] [模式]`通过标准输入输出运行
+        #[cfg(feature = "headless")]
+        Some("--headless") => {
+            snake::headless::run(seed(args.next()), mode(args.next())).map_err(Error::from)
         }
+        _ => run_default(first, args),
     }
 }
 
-/// 墙壁实体
-#[derive(Clone, Debug)]
-pub struct Wall {
-    bricks: Vec<Block>,
-}
-
-impl Wall {
-    /// 边框
-    /// `circus: &[u32; 2]` 是玩家的可移动范围
-    pub fn board_wall(circus: &[u32; 2]) -> Self {
-        let width = circus[0] as i32;
-        let height = circus[1] as i32;
-
-        let wall_color = consts::LIGHTBLUE;
-        let mut bricks = Vec::<Block>::new();
-
-        // 添加上下俩个边框
-        for i in 0..width {
-            let brick_up = Block::new(i, height, Collited::WithWall, wall_color);
-            let brick_down = Block::new(i, 0, Collited::WithWall, wall_color);
-
-            bricks.push(brick_up);
-            bricks.push(brick_down);
-        }
-
-        // 添加左右俩个边框
-        for i in 0..height {
-            let brick_left = Block::new(0, i, Collited::WithWall, wall_color);
-            let brick_right = Block::new(width, i, Collited::WithWall, wall_color);
-
-            bricks.push(brick_left);
-            bricks.push(brick_right);
-        }
-
-        Wall { bricks }
+// 有窗口界面时打开窗口
+#[cfg(feature = "gl")]
+fn run_default(first: Option<String>, mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    // 读取玩家的设置，设置文件无法解析时提示玩家并使用默认设置
+    let mut settings = Settings::load().unwrap_or_else(|e| {
+        eprintln!("Warning: {}", e);
+        Settings::default()
+    });
+
+    // `--seed <种子>`让每一局都使用同一个关卡
+    if let Some("--seed") = first.as_deref() {
+        settings.seed = args.next().and_then(|arg| arg.parse().ok());
     }
 
-    /// 创建一堵随机的墙
-    pub fn randnew(brick_num: Option<u32>, circus: &[u32; 2]) -> Self {
-        let x = rand::thread_rng().gen_range(1, (circus[0] - 1) as i32);
-        let y = rand::thread_rng().gen_range(1, (circus[1] - 1) as i32);
-
-        let mut bricks = Vec::<Block>::new();
-
-        let n = match brick_num {
-            Some(num) => num,
-            None => rand::thread_rng().gen_range(1, min(circus[0], circus[1]) / 2),
-        };
-
-        for i in 0..n {
-            let d = match rand::thread_rng().gen_bool(0.5) {
-                true => 1i32,
-                false => -1i32,
-            };
-            let (dx, dy) = match rand::thread_rng().gen_bool(0.5) {
-                true => (d, 0),
-                false => (0, d),
-            };
-
-            let x_pre = match bricks.get(i as usize) {
-                Some(brick) => brick.pos_x,
-                None => x,
-            };
-
-            let y_pre = match bricks.get(i as usize) {
-                Some(brick) => brick.pos_y,
-                None => y,
-            };
-
-            let brick = Block::new(
-                x_pre + dx * (i as i32),
-                y_pre + dy * (i as i32),
-                Collited::WithWall,
-                consts::LIGHTBLUE,
-            );
+    // 这里的App是主要结构体，理论尺寸由设置决定，默认为(80, 60)
+    let mut app = App::new(settings)?;
 
-            bricks.push(brick);
-        }
-
-        //println!("{:?}/n", bricks);
-
-        Wall { bricks }
+    // `--edit [关卡文件]`直接打开关卡编辑器
+    if let Some("--edit") = first.as_deref() {
+        app.open_editor(args.next().map(PathBuf::from));
     }
-}
 
-/// 食物实体
-#[derive(Clone)]
-pub struct Fruit {
-    block: Block,
+    // 运行程序
+    app.run()
 }
 
-impl Fruit {
-    /// 创建位置随机的Fruit实例
-    pub fn randnew(horizontal_block_num: u32, vertical_block_num: u32) -> Self {
-        Fruit {
-            block: Block {
-                pos_x: rand::thread_rng().gen_range(1, (horizontal_block_num - 1) as i32),
-                pos_y: rand::thread_rng().gen_range(1, (vertical_block_num - 1) as i32),
-                collited: Collited::WithFruit,
-                color: consts::GREEN,
-            },
-        }
-    }
+// 没有窗口界面时退回到终端界面
+#[cfg(all(not(feature = "gl"), feature = "tui"))]
+fn run_default(first: Option<String>, mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    Ok(snake::tui::run(seed(first), mode(args.next()))?)
 }
 
-/// 蛇的实体
-#[derive(Clone)]
-pub struct Snake {
-    head: Block,
-    body: Vec<Block>,
-    velocity: f64,
-    direction: Direction,
-    direction_lock: bool,
-    growth_flag: bool,
+// 只有无界面运行
+#[cfg(all(not(feature = "gl"), not(feature = "tui"), feature = "headless"))]
+fn run_default(first: Option<String>, mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    Ok(snake::headless::run(seed(first), mode(args.next()))?)
 }
 
-impl Snake {
-    // 在移动范围中央创建新的蛇蛇
-    pub fn new(horizontal_block_num: u32, vertical_block_num: u32) -> Self {
-        let center_x = ((horizontal_block_num as f64) * 0.5) as i32;
-        let center_y = ((vertical_block_num as f64) * 0.5) as i32;
-
-        Snake {
-            head: Block {
-                pos_x: center_x,
-                pos_y: center_y,
-                collited: Collited::WithSnake,
-                color: consts::RED,
-            },
-            body: vec![
-                Block {
-                    pos_x: center_x + 1,
-                    pos_y: center_y,
-                    collited: Collited::WithSnake,
-                    color: consts::WHITE,
-                },
-                Block {
-                    pos_x: center_x + 2,
-                    pos_y: center_y,
-                    collited: Collited::WithSnake,
-                    color: consts::WHITE,
-                },
-                Block {
-                    pos_x: center_x + 3,
-                    pos_y: center_y,
-                    collited: Collited::WithSnake,
-                    color: consts::WHITE,
-                },
-                Block {
-                    pos_x: center_x + 4,
-                    pos_y: center_y,
-                    collited: Collited::WithSnake,
-                    color: consts::WHITE,
-                },
-            ],
-            velocity: 6.0,
-            direction: Direction::Left,
-            direction_lock: false,
-            growth_flag: false,
-        }
-    }
-
-    // 检测蛇是否与自己的身体相碰撞
-    fn is_colliting_with_self(&self) -> bool {
-        let mut ans = false;
-        for block in self.body.iter() {
-            match self.head.is_collited_by_block(block) {
-                Collited::NoCollision => continue,
-                _ => {
-                    ans = true;
-                    break;
-                }
-            }
-        }
-
-        ans
-    }
-
-    // 蛇身加长的操作方法
-    fn growth_action(&mut self) {
-        // 设置 growth_flag
-        self.growth_flag = true;
-        // 增加移动速度
-        self.velocity += 0.01;
-    }
-
-    // 移动
-    fn moving(&mut self) {
-        // 坐标移动
-        let (x, y) = match self.direction {
-            Direction::Up => (0, -1),
-            Direction::Down => (0, 1),
-            Direction::Left => (-1, 0),
-            Direction::Right => (1, 0),
-        };
-
-        // 克隆当前的坐标，会成为身体的一部分
-        let mut pre_block = self.head.clone();
-        pre_block.color = consts::WHITE;
-
-        // 更新蛇头坐标
-        self.head.pos_x += x;
-        self.head.pos_y += y;
-
-        // 通过将蛇体的当前块推到新向量来“移动”蛇
-        let mut blocks = Vec::new();
-        for block in self.body.iter_mut() {
-            blocks.push(pre_block);
-            pre_block = block.clone();
-        }
-
-        // 如果设置了增长标志，请不要浪费任何块。
-        if self.growth_flag {
-            blocks.push(pre_block);
-            self.growth_flag = false;
-        }
+// 没有任何界面
+#[cfg(not(any(feature = "gl", feature = "tui", feature = "headless")))]
+fn run_default(_first: Option<String>, _args: impl Iterator<Item = String>) -> Result<(), Error> {
+    eprintln!("This build has no frontend, enable the gl, tui or headless feature");
+    Ok(())
+}
 
-        // 分配新的身体
-        self.body = blocks;
-    }
+// 命令行中的种子，没有指定或无法解析时随机
+#[cfg(any(feature = "tui", feature = "headless"))]
+fn seed(arg: Option<String>) -> u64 {
+    arg.and_then(|arg| arg.parse().ok())
+        .unwrap_or_else(rand::random)
 }
 
-/// 游戏状态机
-#[derive(Debug, PartialEq, Clone)]
-pub enum GameStatus {
-    TIMEOUT,  // 游戏暂停
-    GAMING,  // 游戏进行中
-    GAMEOVER, // 游戏结束
-    RESTART,  // 游戏重启
+// 命令行中的模式，按模式名称匹配，不区分大小写，空格可以写成`-`，没有指定时为经典模式
+#[cfg(any(feature = "tui", feature = "headless"))]
+fn mode(arg: Option<String>) -> GameMode {
+    let arg = match arg {
+        Some(arg) => arg.replace('-', " "),
+        None => return GameMode::Classic,
+    };
+
+    GameMode::ALL
+        .iter()
+        .find(|mode| mode.name().eq_ignore_ascii_case(&arg))
+        .copied()
+        .unwrap_or_else(|| {
+            eprintln!(
+                "Unknown mode: {}, playing {}",
+                arg,
+                GameMode::Classic.name()
+            );
+            GameMode::Classic
+        })
 }
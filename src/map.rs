@@ -0,0 +1,110 @@
+use crate::{obstacle::Portal, Direction, Fruit, Snake, Wall};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// 关卡文件中描述的关卡布局
+/// 坐标按`board_size`设计，在其他尺寸的移动空间中按比例缩放
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelMap {
+    pub name: String,                 // 关卡名称
+    pub board_size: [u32; 2],         // 设计关卡时的移动空间尺寸，为0时不缩放
+    pub walls: Vec<[i32; 2]>,         // 墙壁所在的格子
+    pub portals: Vec<[[i32; 2]; 2]>,  // 成对的传送门
+    pub random_portals: u32,          // 每局额外随机生成的传送门对数
+    pub spawn: Option<[i32; 2]>,      // 蛇头出生的位置，为空时在中央
+    pub direction: Option<Direction>, // 蛇出生时的方向，为空时向左
+    pub fruit: Option<[i32; 2]>,      // 第一个食物的位置，为空时随机
+}
+
+impl LevelMap {
+    /// 解析TOML格式的关卡
+    pub fn parse(content: &str) -> Result<Self, String> {
+        toml::from_str(content).map_err(|e| e.to_string())
+    }
+
+    /// 读取关卡文件
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| LevelMap::parse(&content))
+    }
+
+    /// 把关卡写入关卡文件
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        fs::write(path, content)
+    }
+
+    /// 把关卡中的坐标换算到`circus`尺寸的移动空间，落在边框上或之外时返回None
+    pub fn scale(&self, cell: [i32; 2], circus: &[u32; 2]) -> Option<(i32, i32)> {
+        let (x, y) = match self.board_size {
+            [0, _] | [_, 0] => (cell[0], cell[1]),
+            [width, height] => (
+                cell[0] * circus[0] as i32 / width as i32,
+                cell[1] * circus[1] as i32 / height as i32,
+            ),
+        };
+
+        match x > 0 && y > 0 && x < circus[0] as i32 && y < circus[1] as i32 {
+            true => Some((x, y)),
+            false => None,
+        }
+    }
+
+    /// 关卡中的墙壁
+    pub fn walls(&self, circus: &[u32; 2]) -> Vec<Wall> {
+        let cells: Vec<(i32, i32)> = self
+            .walls
+            .iter()
+            .filter_map(|&cell| self.scale(cell, circus))
+            .collect();
+
+        match cells.is_empty() {
+            true => Vec::new(),
+            false => vec![Wall::from_cells(&cells)],
+        }
+    }
+
+    /// 按关卡中的出生位置和方向创建的蛇
+    pub fn snake(&self, circus: &[u32; 2]) -> Snake {
+        let spawn = self.spawn.and_then(|cell| self.scale(cell, circus));
+        let snake = Snake::new(circus[0], circus[1]);
+
+        match (spawn, &self.direction) {
+            (None, None) => snake,
+            (spawn, direction) => {
+                let (x, y) = spawn.unwrap_or((snake.head.pos_x, snake.head.pos_y));
+                Snake::at(x, y, direction.clone().unwrap_or(Direction::Left))
+            }
+        }
+    }
+
+    /// 关卡中指定的第一个食物
+    pub fn fruit(&self, circus: &[u32; 2]) -> Option<Fruit> {
+        self.fruit
+            .and_then(|cell| self.scale(cell, circus))
+            .map(|(x, y)| Fruit::at(x, y))
+    }
+
+    /// 关卡中固定位置的传送门，两端重合的传送门会被忽略
+    pub fn portals(&self, circus: &[u32; 2]) -> Vec<Portal> {
+        self.portals
+            .iter()
+            .filter_map(
+                |&[a, b]| match (self.scale(a, circus), self.scale(b, circus)) {
+                    (Some(a), Some(b)) if a != b => Some(Portal::new(a, b)),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+}
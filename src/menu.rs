@@ -0,0 +1,331 @@
+use crate::{
+    consts,
+    hud::HudItem,
+    level::Level,
+    locale::{tr, trf},
+    settings::{Setting, Settings},
+    GameMode,
+};
+
+/// 菜单页面
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MenuPage {
+    Title,          // 标题界面
+    ModeSelect,     // 模式选择
+    LevelSelect,    // 关卡选择
+    Options,        // 选项
+    GameOptions,    // 游戏选项
+    DisplayOptions, // 显示选项
+    HudOptions,     // HUD选项
+    AudioOptions,   // 声音选项
+    ControlOptions, // 按键选项
+    HighScores,     // 高分榜
+    Pause,          // 暂停菜单
+    GameOver,       // 游戏结束
+}
+
+/// 菜单项被选中后执行的动作
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MenuAction {
+    Continue,
+    NewGame,
+    Mode(GameMode),
+    Level(Level),
+    Options,
+    Open(MenuPage),
+    Setting(Setting),
+    HighScores,
+    Resume,
+    Restart,
+    SaveReplay,
+    Editor,
+    MainMenu,
+    Back,
+    Quit,
+}
+
+impl MenuAction {
+    /// 菜单项上按界面语言显示的文字，设置项会显示当前的值
+    pub fn label(&self, settings: &Settings) -> String {
+        match self {
+            MenuAction::Continue => tr("Continue").to_string(),
+            MenuAction::NewGame => tr("New game").to_string(),
+            MenuAction::Mode(mode) => tr(mode.name()).to_string(),
+            MenuAction::Level(level) => tr(level.name()).to_string(),
+            MenuAction::Options => tr("Options").to_string(),
+            MenuAction::Open(MenuPage::GameOptions) => tr("Game").to_string(),
+            MenuAction::Open(MenuPage::DisplayOptions) => tr("Display").to_string(),
+            MenuAction::Open(MenuPage::HudOptions) => tr("HUD").to_string(),
+            MenuAction::Open(MenuPage::AudioOptions) => tr("Sound").to_string(),
+            MenuAction::Open(MenuPage::ControlOptions) => tr("Controls").to_string(),
+            MenuAction::Open(page) => format!("{:?}", page),
+            MenuAction::Setting(setting) => {
+                trf("{}: {}", &[&tr(setting.name()), &settings.value(*setting)])
+            }
+            MenuAction::HighScores => tr("High scores").to_string(),
+            MenuAction::Resume => tr("Resume").to_string(),
+            MenuAction::Restart => tr("Restart").to_string(),
+            MenuAction::SaveReplay => tr("Save replay").to_string(),
+            MenuAction::Editor => tr("Level editor").to_string(),
+            MenuAction::MainMenu => tr("Main menu").to_string(),
+            MenuAction::Back => tr("Back").to_string(),
+            MenuAction::Quit => tr("Quit").to_string(),
+        }
+    }
+}
+
+/// 菜单状态
+#[derive(Debug, Clone)]
+pub struct Menu {
+    pub page: MenuPage,          // 当前页面
+    pub selected: usize,         // 当前选中的菜单项
+    pub can_continue: bool,      // 是否有可以继续的存档
+    pub message: Option<String>, // 最近一次保存或读取的结果，切换页面时清除
+    history: Vec<MenuPage>,      // 返回时回到的页面
+}
+
+impl Menu {
+    /// 创建停留在指定页面的菜单
+    pub fn new(page: MenuPage) -> Self {
+        Menu {
+            page,
+            selected: 0,
+            can_continue: false,
+            message: None,
+            history: Vec::new(),
+        }
+    }
+
+    /// 页面标题，按界面语言翻译
+    pub fn title(&self) -> &'static str {
+        tr(match self.page {
+            MenuPage::Title => "SNAKE",
+            MenuPage::ModeSelect => "Select mode",
+            MenuPage::LevelSelect => "Select level",
+            MenuPage::Options => "Options",
+            MenuPage::GameOptions => "Game options",
+            MenuPage::DisplayOptions => "Display options",
+            MenuPage::HudOptions => "HUD",
+            MenuPage::AudioOptions => "Sound options",
+            MenuPage::ControlOptions => "Controls",
+            MenuPage::HighScores => "High scores",
+            MenuPage::Pause => "Paused",
+            MenuPage::GameOver => "Game over",
+        })
+    }
+
+    /// 当前页面的所有菜单项
+    pub fn items(&self) -> Vec<MenuAction> {
+        match self.page {
+            MenuPage::Title => {
+                let mut items = vec![
+                    MenuAction::NewGame,
+                    MenuAction::Editor,
+                    MenuAction::Options,
+                    MenuAction::HighScores,
+                    MenuAction::Quit,
+                ];
+                // 有存档时可以继续上次的游戏
+                if self.can_continue {
+                    items.insert(0, MenuAction::Continue);
+                }
+                items
+            }
+            MenuPage::ModeSelect => {
+                let mut items: Vec<MenuAction> =
+                    GameMode::ALL.iter().map(|&mode| MenuAction::Mode(mode)).collect();
+                items.push(MenuAction::Back);
+                items
+            }
+            MenuPage::LevelSelect => {
+                let mut items: Vec<MenuAction> =
+                    Level::ALL.iter().map(|&level| MenuAction::Level(level)).collect();
+                items.push(MenuAction::Back);
+                items
+            }
+            MenuPage::Options => vec![
+                MenuAction::Open(MenuPage::GameOptions),
+                MenuAction::Open(MenuPage::DisplayOptions),
+                MenuAction::Open(MenuPage::AudioOptions),
+                MenuAction::Open(MenuPage::ControlOptions),
+                MenuAction::Back,
+            ],
+            MenuPage::GameOptions => vec![
+                MenuAction::Setting(Setting::BoardSize),
+                MenuAction::Setting(Setting::Speed),
+                MenuAction::Setting(Setting::WallDensity),
+                MenuAction::Setting(Setting::Generator),
+                MenuAction::Setting(Setting::Seed),
+                MenuAction::Back,
+            ],
+            MenuPage::DisplayOptions => vec![
+                MenuAction::Setting(Setting::Language),
+                MenuAction::Setting(Setting::Theme),
+                MenuAction::Setting(Setting::Sprites),
+                MenuAction::Open(MenuPage::HudOptions),
+                MenuAction::Setting(Setting::Fullscreen),
+                MenuAction::Back,
+            ],
+            MenuPage::HudOptions => {
+                let mut items: Vec<MenuAction> = HudItem::ALL
+                    .iter()
+                    .map(|&item| MenuAction::Setting(Setting::Hud(item)))
+                    .collect();
+                items.push(MenuAction::Back);
+                items
+            }
+            MenuPage::AudioOptions => vec![
+                MenuAction::Setting(Setting::Volume),
+                MenuAction::Setting(Setting::MusicVolume),
+                MenuAction::Setting(Setting::Mute),
+                MenuAction::Back,
+            ],
+            MenuPage::ControlOptions => vec![
+                MenuAction::Setting(Setting::KeyUp),
+                MenuAction::Setting(Setting::KeyDown),
+                MenuAction::Setting(Setting::KeyLeft),
+                MenuAction::Setting(Setting::KeyRight),
+                MenuAction::Setting(Setting::KeyPause),
+                MenuAction::Setting(Setting::KeyMute),
+                MenuAction::Setting(Setting::MouseControl),
+                MenuAction::Setting(Setting::Controller(0)),
+                MenuAction::Setting(Setting::Controller(1)),
+                MenuAction::Setting(Setting::Deadzone),
+                MenuAction::Back,
+            ],
+            MenuPage::HighScores => vec![MenuAction::Back],
+            MenuPage::Pause => vec![
+                MenuAction::Resume,
+                MenuAction::Restart,
+                MenuAction::Options,
+                MenuAction::MainMenu,
+                MenuAction::Quit,
+            ],
+            MenuPage::GameOver => vec![
+                MenuAction::Restart,
+                MenuAction::SaveReplay,
+                MenuAction::MainMenu,
+                MenuAction::Quit,
+            ],
+        }
+    }
+
+    /// 当前选中的菜单项
+    pub fn current(&self) -> Option<MenuAction> {
+        self.items().get(self.selected).copied()
+    }
+
+    /// 进入新页面，记录当前页面以便返回
+    pub fn open(&mut self, page: MenuPage) {
+        self.history.push(self.page);
+        self.page = page;
+        self.selected = 0;
+        self.message = None;
+    }
+
+    /// 返回上一个页面，没有上一个页面时返回false
+    pub fn back(&mut self) -> bool {
+        match self.history.pop() {
+            Some(page) => {
+                self.page = page;
+                self.selected = 0;
+                self.message = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 清空历史并跳转到指定页面
+    pub fn reset(&mut self, page: MenuPage) {
+        self.history.clear();
+        self.page = page;
+        self.selected = 0;
+        self.message = None;
+    }
+
+    /// 选中上一项
+    pub fn select_prev(&mut self) {
+        let len = self.items().len();
+        self.selected = (self.selected + len - 1) % len;
+    }
+
+    /// 选中下一项
+    pub fn select_next(&mut self) {
+        let len = self.items().len();
+        self.selected = (self.selected + 1) % len;
+    }
+
+    /// 第`index`个菜单项在窗口中的矩形区域
+    /// 游戏结束页面的上半部分用来显示统计数据，菜单项放在下方
+    /// 菜单项太多放不下时缩小间距和高度，保证最后一项也在窗口中
+    pub fn item_rect(&self, index: usize, window_size: &[f64; 2]) -> [f64; 4] {
+        let top = match self.page {
+            MenuPage::GameOver => 0.62,
+            _ => 0.35,
+        } * window_size[1];
+        let count = self.items().len().max(1) as f64;
+        let available = (window_size[1] - consts::MENU_BOTTOM_MARGIN - top).max(0.0);
+        let step = (consts::MENU_ITEM_HEIGHT + consts::MENU_ITEM_SPACING).min(available / count);
+        let height = consts::MENU_ITEM_HEIGHT.min(step * 0.85);
+
+        let x = (window_size[0] - consts::MENU_ITEM_WIDTH) * 0.5;
+        let y = top + (index as f64) * step;
+
+        [x, y, consts::MENU_ITEM_WIDTH, height]
+    }
+
+    /// 找到鼠标位置下的菜单项
+    pub fn item_at(&self, pos: &[f64; 2], window_size: &[f64; 2]) -> Option<usize> {
+        (0..self.items().len()).find(|&index| {
+            let rect = self.item_rect(index, window_size);
+
+            pos[0] >= rect[0]
+                && pos[0] <= rect[0] + rect[2]
+                && pos[1] >= rect[1]
+                && pos[1] <= rect[1] + rect[3]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_pages_keep_the_normal_spacing() {
+        let menu = Menu::new(MenuPage::Title);
+        let window = [640.0, 480.0];
+
+        assert_eq!(menu.item_rect(0, &window)[1], 168.0);
+        assert_eq!(
+            menu.item_rect(1, &window)[1],
+            168.0 + consts::MENU_ITEM_HEIGHT + consts::MENU_ITEM_SPACING
+        );
+        assert_eq!(menu.item_rect(1, &window)[3], consts::MENU_ITEM_HEIGHT);
+    }
+
+    #[test]
+    fn long_pages_fit_in_the_window() {
+        let pages = [
+            MenuPage::HudOptions,
+            MenuPage::ControlOptions,
+            MenuPage::GameOver,
+        ];
+        for &page in pages.iter() {
+            for window in [[640.0, 480.0], [640.0, 300.0], [1024.0, 768.0]].iter() {
+                let menu = Menu::new(page);
+                let last = menu.items().len() - 1;
+                let rect = menu.item_rect(last, window);
+                let first = menu.item_rect(0, window);
+
+                assert!(rect[1] + rect[3] <= window[1] - consts::MENU_BOTTOM_MARGIN);
+                assert!(first[1] + first[3] <= menu.item_rect(1, window)[1]);
+
+                // 鼠标可以选中最后一项
+                let center = [rect[0] + rect[2] * 0.5, rect[1] + rect[3] * 0.5];
+                assert_eq!(menu.item_at(&center, window), Some(last));
+            }
+        }
+    }
+}
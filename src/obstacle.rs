@@ -0,0 +1,330 @@
+use crate::{
+    collision::{Collision, Collited},
+    Block, Snake, Wall,
+};
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 动态墙壁的变化方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Behavior {
+    /// 沿`step`方向来回滑动，每`interval`步移动一格，最远离开起点`distance`格
+    Sliding {
+        step: (i32, i32),
+        distance: u32,
+        interval: u32,
+    },
+    /// 本局吃到`fruits`个食物之后出现
+    Appearing { fruits: u32 },
+    /// 每`period`步在实体和虚影之间切换一次，`offset`用来错开不同墙壁的节奏
+    Blinking { period: u32, offset: u32 },
+}
+
+/// 会随时间变化的墙壁，不是实体的时候可以穿过
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DynamicWall {
+    pub wall: Wall,
+    pub behavior: Behavior,
+    pub solid: bool, // 当前是否会被撞上
+    color: [f32; 4], // 实体状态下的颜色
+    offset: u32,     // 滑动墙壁离起点的距离
+    forward: bool,   // 滑动墙壁是否正在远离起点
+}
+
+impl DynamicWall {
+    /// 创建动态墙壁，会出现的墙壁一开始不是实体
+    pub fn new(wall: Wall, behavior: Behavior) -> Self {
+        let solid = match behavior {
+            Behavior::Appearing { fruits } => fruits == 0,
+            _ => true,
+        };
+
+        DynamicWall {
+            wall,
+            behavior,
+            solid,
+            color: [1.0; 4],
+            offset: 0,
+            forward: true,
+        }
+    }
+
+    /// 修改墙壁的颜色，不是实体时画成半透明的虚影
+    pub fn paint(&mut self, color: [f32; 4]) {
+        self.color = color;
+
+        let alpha = match self.solid {
+            true => color[3],
+            false => color[3] * 0.3,
+        };
+        self.wall.paint([color[0], color[1], color[2], alpha]);
+    }
+
+    /// 经过一步
+    /// `ticks`是本局经过的步数，`fruits`是本局吃到的食物数量
+    /// 墙壁不会在蛇所在的位置变成实体，也不会滑到蛇身上，避免玩家无处可躲
+    pub fn update(&mut self, ticks: u64, fruits: u32, snake: &Snake) {
+        let solid = match self.behavior {
+            Behavior::Sliding {
+                step,
+                distance,
+                interval,
+            } => {
+                if distance > 0 && ticks.is_multiple_of(interval.max(1) as u64) {
+                    self.slide(step, distance, snake);
+                }
+                true
+            }
+            Behavior::Appearing { fruits: after } => fruits >= after,
+            Behavior::Blinking { period, offset } => {
+                ((ticks + offset as u64) / period.max(1) as u64).is_multiple_of(2)
+            }
+        };
+
+        if solid != self.solid {
+            self.solid = solid && !self.overlaps(snake, (0, 0));
+            self.paint(self.color);
+        }
+    }
+
+    // 沿路径移动一格，到达两端时掉头
+    // 下一个位置压到蛇时掉头，掉头也会压到蛇或者已经在起点时停在原地
+    fn slide(&mut self, step: (i32, i32), distance: u32, snake: &Snake) {
+        if self.offset >= distance {
+            self.forward = false;
+        } else if self.offset == 0 {
+            self.forward = true;
+        }
+
+        if self.overlaps(snake, self.heading(step)) {
+            let can_reverse = match self.forward {
+                true => self.offset > 0,
+                false => self.offset < distance,
+            };
+            if !can_reverse || self.overlaps(snake, self.heading((-step.0, -step.1))) {
+                return;
+            }
+            self.forward = !self.forward;
+        }
+
+        let (dx, dy) = self.heading(step);
+        self.wall.shift(dx, dy);
+        match self.forward {
+            true => self.offset += 1,
+            false => self.offset -= 1,
+        }
+    }
+
+    // 滑动墙壁当前移动的方向
+    fn heading(&self, step: (i32, i32)) -> (i32, i32) {
+        match self.forward {
+            true => step,
+            false => (-step.0, -step.1),
+        }
+    }
+
+    // 墙壁平移`(dx, dy)`之后是否压到蛇
+    fn overlaps(&self, snake: &Snake, (dx, dy): (i32, i32)) -> bool {
+        std::iter::once(&snake.head)
+            .chain(snake.body.iter())
+            .any(|block| {
+                self.wall
+                    .bricks
+                    .iter()
+                    .any(|brick| (brick.pos_x + dx, brick.pos_y + dy) == block.cell())
+            })
+    }
+}
+
+impl Collision for DynamicWall {
+    fn is_colliting<T: Collision>(&self, object: &T) -> Collited {
+        match self.solid {
+            true => self.wall.is_colliting(object),
+            false => Collited::NoCollision,
+        }
+    }
+
+    fn is_collited_by_block(&self, block: &Block) -> Collited {
+        match self.solid {
+            true => self.wall.is_collited_by_block(block),
+            false => Collited::NoCollision,
+        }
+    }
+}
+
+/// 一对传送门，蛇头进入一端就会从另一端出来
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Portal {
+    pub ends: [Block; 2],
+}
+
+impl Portal {
+    /// 在`a`和`b`两个格子之间创建传送门
+    pub fn new(a: (i32, i32), b: (i32, i32)) -> Self {
+        Portal {
+            ends: [
+                Block::new(a.0, a.1, Collited::NoCollision, [1.0; 4]),
+                Block::new(b.0, b.1, Collited::NoCollision, [1.0; 4]),
+            ],
+        }
+    }
+
+    /// 在随机位置创建传送门
+    /// 两端都不会落在`taken`中的格子上，也不在蛇出生的那一行，找不到位置时返回None
+    pub fn randnew<R: Rng>(
+        circus: &[u32; 2],
+        taken: &HashSet<(i32, i32)>,
+        rng: &mut R,
+    ) -> Option<Self> {
+        let spawn_row = ((circus[1] as f64) * 0.5) as i32;
+        let mut pick = |except: Option<(i32, i32)>| {
+            (0..100)
+                .map(|_| {
+                    (
+                        rng.gen_range(1, circus[0] as i32 - 1),
+                        rng.gen_range(1, circus[1] as i32 - 1),
+                    )
+                })
+                .find(|cell| cell.1 != spawn_row && !taken.contains(cell) && Some(*cell) != except)
+        };
+
+        let a = pick(None)?;
+        let b = pick(Some(a))?;
+
+        Some(Portal::new(a, b))
+    }
+
+    /// 两端所在的格子
+    pub fn cells(&self) -> [(i32, i32); 2] {
+        let [a, b] = &self.ends;
+
+        [(a.pos_x, a.pos_y), (b.pos_x, b.pos_y)]
+    }
+
+    /// 位于`(x, y)`的一端所连接的另一端，`(x, y)`不是传送门时返回None
+    pub fn exit(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        match (self.cells()[0], self.cells()[1]) {
+            (a, b) if a == (x, y) => Some(b),
+            (a, b) if b == (x, y) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// 修改传送门的颜色
+    pub fn paint(&mut self, color: [f32; 4]) {
+        for end in self.ends.iter_mut() {
+            end.color = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 蛇头在(20, 15)，蛇身向右延伸
+    fn snake() -> Snake {
+        Snake::new(40, 30)
+    }
+
+    // 从`start`出发沿x轴滑动的单块墙壁
+    fn sliding(start: (i32, i32), distance: u32) -> DynamicWall {
+        let behavior = Behavior::Sliding {
+            step: (1, 0),
+            distance,
+            interval: 1,
+        };
+        DynamicWall::new(Wall::from_cells(&[start]), behavior)
+    }
+
+    // 墙壁第一块砖的横坐标
+    fn x(wall: &DynamicWall) -> i32 {
+        wall.wall.bricks()[0].cell().0
+    }
+
+    #[test]
+    fn sliding_walls_go_back_and_forth() {
+        let snake = snake();
+        let mut wall = sliding((5, 5), 2);
+
+        let mut path = Vec::new();
+        for ticks in 1..=5 {
+            wall.update(ticks, 0, &snake);
+            path.push(x(&wall));
+        }
+        assert_eq!(path, vec![6, 7, 6, 5, 6]);
+    }
+
+    #[test]
+    fn sliding_walls_turn_back_before_the_snake() {
+        let snake = snake();
+        let mut wall = sliding((17, 15), 5);
+
+        let mut path = Vec::new();
+        for ticks in 1..=4 {
+            wall.update(ticks, 0, &snake);
+            path.push(x(&wall));
+        }
+        assert_eq!(path, vec![18, 19, 18, 17]);
+    }
+
+    #[test]
+    fn sliding_walls_hold_when_they_cannot_turn_back() {
+        let snake = snake();
+        let mut wall = sliding((19, 15), 5);
+
+        for ticks in 1..=3 {
+            wall.update(ticks, 0, &snake);
+            assert_eq!(x(&wall), 19);
+        }
+    }
+
+    #[test]
+    fn blinking_walls_stay_open_under_the_snake() {
+        let snake = snake();
+        let behavior = Behavior::Blinking {
+            period: 2,
+            offset: 0,
+        };
+        let mut open = DynamicWall::new(Wall::from_cells(&[(5, 5)]), behavior);
+        let mut covered = DynamicWall::new(Wall::from_cells(&[(21, 15)]), behavior);
+
+        let mut solid = Vec::new();
+        for ticks in 1..=5 {
+            open.update(ticks, 0, &snake);
+            covered.update(ticks, 0, &snake);
+            solid.push(open.solid);
+        }
+        assert_eq!(solid, vec![true, false, false, true, true]);
+        assert!(!covered.solid);
+    }
+
+    #[test]
+    fn appearing_walls_wait_for_fruits_and_for_the_snake_to_leave() {
+        let mut snake = snake();
+        let behavior = Behavior::Appearing { fruits: 3 };
+        let mut wall = DynamicWall::new(Wall::from_cells(&[(20, 15)]), behavior);
+        assert!(!wall.solid);
+
+        wall.update(1, 2, &snake);
+        assert!(!wall.solid);
+        wall.update(2, 3, &snake);
+        assert!(!wall.solid);
+        assert_eq!(
+            wall.is_collited_by_block(&snake.head),
+            Collited::NoCollision
+        );
+
+        // 蛇离开之后才出现
+        for _ in 0..snake.length() {
+            snake.moving();
+        }
+        wall.update(3, 3, &snake);
+        assert!(wall.solid);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{collision::Collited, consts, Block};
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// 道具的种类
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PowerUpKind {
+    SlowMotion,  // 暂时降低蛇的速度
+    Ghost,       // 可以穿过自己的身体
+    WallBreaker, // 撞上墙壁时摧毁一块砖而不是死亡
+    Magnet,      // 把食物吸向蛇头
+    DoubleScore, // 吃到食物得双倍分数
+}
+
+impl PowerUpKind {
+    /// 所有道具
+    pub const ALL: [PowerUpKind; 5] = [
+        PowerUpKind::SlowMotion,
+        PowerUpKind::Ghost,
+        PowerUpKind::WallBreaker,
+        PowerUpKind::Magnet,
+        PowerUpKind::DoubleScore,
+    ];
+
+    /// 道具名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerUpKind::SlowMotion => "Slow",
+            PowerUpKind::Ghost => "Ghost",
+            PowerUpKind::WallBreaker => "Breaker",
+            PowerUpKind::Magnet => "Magnet",
+            PowerUpKind::DoubleScore => "x2",
+        }
+    }
+
+    /// 效果持续的步数
+    pub fn duration(&self) -> u32 {
+        match self {
+            PowerUpKind::SlowMotion => 40,
+            PowerUpKind::Ghost => 40,
+            PowerUpKind::WallBreaker => 100,
+            PowerUpKind::Magnet => 60,
+            PowerUpKind::DoubleScore => 80,
+        }
+    }
+
+    /// 在`ALL`中的位置，用来从主题中选取颜色
+    pub fn index(&self) -> usize {
+        PowerUpKind::ALL
+            .iter()
+            .position(|kind| kind == self)
+            .unwrap_or(0)
+    }
+}
+
+/// 场上的道具实体
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    pub block: Block,
+    pub lifetime: u32, // 道具消失前剩余的步数
+}
+
+impl PowerUp {
+    /// 在随机位置创建随机种类的道具
+    pub fn randnew<R: Rng>(
+        horizontal_block_num: u32,
+        vertical_block_num: u32,
+        rng: &mut R,
+    ) -> Self {
+        let kind = PowerUpKind::ALL[rng.gen_range(0, PowerUpKind::ALL.len())];
+
+        PowerUp {
+            kind,
+            block: Block::new(
+                rng.gen_range(1, (horizontal_block_num - 1) as i32),
+                rng.gen_range(1, (vertical_block_num - 1) as i32),
+                Collited::WithPowerUp,
+                consts::PINK,
+            ),
+            lifetime: consts::POWER_UP_LIFETIME,
+        }
+    }
+
+    /// 修改道具的颜色
+    pub fn paint(&mut self, color: [f32; 4]) {
+        self.block.color = color;
+    }
+}
+
+/// 生效中的道具效果
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Effect {
+    pub kind: PowerUpKind,
+    pub remaining: u32, // 剩余的步数
+}
+
+/// 所有生效中的道具效果
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Effects {
+    active: Vec<Effect>,
+}
+
+impl Effects {
+    /// 没有任何效果
+    pub fn new() -> Self {
+        Effects::default()
+    }
+
+    /// 开始一个效果，已经生效时重新计时
+    pub fn activate(&mut self, kind: PowerUpKind) {
+        match self.active.iter_mut().find(|effect| effect.kind == kind) {
+            Some(effect) => effect.remaining = kind.duration(),
+            None => self.active.push(Effect {
+                kind,
+                remaining: kind.duration(),
+            }),
+        }
+    }
+
+    /// 提前结束一个效果
+    pub fn consume(&mut self, kind: PowerUpKind) {
+        self.active.retain(|effect| effect.kind != kind);
+    }
+
+    /// 效果是否生效中
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.active.iter().any(|effect| effect.kind == kind)
+    }
+
+    /// 所有生效中的效果
+    pub fn active(&self) -> &[Effect] {
+        &self.active
+    }
+
+    /// 经过一步，返回到期的效果
+    pub fn tick(&mut self) -> Vec<PowerUpKind> {
+        for effect in self.active.iter_mut() {
+            effect.remaining = effect.remaining.saturating_sub(1);
+        }
+
+        let expired = self
+            .active
+            .iter()
+            .filter(|effect| effect.remaining == 0)
+            .map(|effect| effect.kind)
+            .collect();
+        self.active.retain(|effect| effect.remaining > 0);
+
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effects_last_for_their_duration() {
+        let mut effects = Effects::new();
+        effects.activate(PowerUpKind::Ghost);
+
+        for _ in 1..PowerUpKind::Ghost.duration() {
+            assert!(effects.tick().is_empty());
+            assert!(effects.is_active(PowerUpKind::Ghost));
+        }
+        assert_eq!(effects.tick(), vec![PowerUpKind::Ghost]);
+        assert!(!effects.is_active(PowerUpKind::Ghost));
+        assert!(effects.active().is_empty());
+    }
+
+    #[test]
+    fn activating_again_restarts_the_timer() {
+        let mut effects = Effects::new();
+        effects.activate(PowerUpKind::Magnet);
+        effects.tick();
+        effects.activate(PowerUpKind::Magnet);
+
+        assert_eq!(
+            effects.active(),
+            &[Effect {
+                kind: PowerUpKind::Magnet,
+                remaining: PowerUpKind::Magnet.duration(),
+            }]
+        );
+    }
+
+    #[test]
+    fn effects_tick_and_end_independently() {
+        let mut effects = Effects::new();
+        effects.activate(PowerUpKind::SlowMotion);
+        effects.activate(PowerUpKind::WallBreaker);
+
+        effects.consume(PowerUpKind::WallBreaker);
+        assert!(effects.is_active(PowerUpKind::SlowMotion));
+        assert!(!effects.is_active(PowerUpKind::WallBreaker));
+
+        for _ in 1..PowerUpKind::SlowMotion.duration() {
+            effects.tick();
+        }
+        assert_eq!(effects.tick(), vec![PowerUpKind::SlowMotion]);
+        assert!(effects.tick().is_empty());
+    }
+}
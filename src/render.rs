@@ -1,31 +1,35 @@
-use crate::{app::App, consts, Block, Fruit, GameStatus, Snake, Wall};
+use crate::{
+    app::App,
+    consts,
+    event::{GameEvent, Subscriber},
+    layout::Layout,
+    level::Level,
+    hud,
+    locale::{tr, trf},
+    menu::{MenuAction, MenuPage},
+    obstacle::{DynamicWall, Portal},
+    powerup::PowerUp,
+    tileset::{self, Sprite, Tileset},
+    Block, Fruit, GameMode, GameStatus, Snake, Wall,
+};
+use graphics::character::CharacterCache;
 use graphics::*;
-use opengl_graphics::{Filter, GlGraphics, GlyphCache, TextureSettings};
+use opengl_graphics::{GlGraphics, GlyphCache};
 use piston::RenderArgs;
 
 pub trait Render {
-    fn render(&mut self, circus: &[u32; 2], args: &RenderArgs, gl: &mut GlGraphics, c: Context);
+    fn render(&mut self, layout: &Layout, args: &RenderArgs, gl: &mut GlGraphics, c: Context);
 
-    fn renderable_rect(circus: &[u32; 2], block: &Block, args: &RenderArgs) -> [f64; 4] {
-        let block_size_x = args.window_size[0] / (circus[0] as f64);
-        let block_size_y = args.window_size[1] / (circus[1] as f64);
-        let window_pos_x = (block.pos_x as f64) * block_size_x;
-        let window_pos_y = (block.pos_y as f64) * block_size_y;
-
-        rectangle::rectangle_by_corners(
-            window_pos_x - block_size_x * 0.5,
-            window_pos_y - block_size_y * 0.5,
-            window_pos_x + block_size_x * 0.5,
-            window_pos_y + block_size_y * 0.5,
-        )
+    fn renderable_rect(layout: &Layout, block: &Block) -> [f64; 4] {
+        layout.cell_rect(block.pos_x, block.pos_y)
     }
 }
 
 impl Render for Block {
-    fn render(&mut self, circus: &[u32; 2], args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
+    fn render(&mut self, layout: &Layout, _args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
         rectangle(
             self.color,
-            Block::renderable_rect(circus, &self, args),
+            Block::renderable_rect(layout, self),
             c.transform,
             gl,
         );
@@ -33,61 +37,118 @@ impl Render for Block {
 }
 
 impl Render for Wall {
-    fn render(&mut self, circus: &[u32; 2], args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
+    fn render(&mut self, layout: &Layout, args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
         for block in self.bricks.iter_mut() {
-            block.render(circus, args, gl, c);
+            block.render(layout, args, gl, c);
         }
     }
 }
 
 impl Render for Fruit {
-    fn render(&mut self, circus: &[u32; 2], args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
-        self.block.render(circus, args, gl, c);
+    fn render(&mut self, layout: &Layout, args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
+        self.block.render(layout, args, gl, c);
+    }
+}
+
+impl Render for PowerUp {
+    fn render(&mut self, layout: &Layout, _args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
+        // 道具画成圆形，与方形的食物区分开
+        ellipse(
+            self.block.color,
+            PowerUp::renderable_rect(layout, &self.block),
+            c.transform,
+            gl,
+        );
+    }
+}
+
+impl Render for DynamicWall {
+    fn render(&mut self, layout: &Layout, args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
+        self.wall.render(layout, args, gl, c);
+    }
+}
+
+impl Render for Portal {
+    fn render(&mut self, layout: &Layout, _args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
+        // 传送门画成圆环，中间可以看到穿过的蛇
+        for end in self.ends.iter() {
+            Ellipse::new_border(end.color, (layout.cell * 0.15).max(1.0)).draw(
+                Portal::renderable_rect(layout, end),
+                &c.draw_state,
+                c.transform,
+                gl,
+            );
+        }
     }
 }
 
 impl Render for Snake {
-    fn render(&mut self, circus: &[u32; 2], args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
-        self.head.render(circus, args, gl, c);
+    fn render(&mut self, layout: &Layout, args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
+        self.head.render(layout, args, gl, c);
 
         for block in self.body.iter_mut() {
-            block.render(circus, args, gl, c);
+            block.render(layout, args, gl, c);
         }
     }
 }
 
-impl Render for App<'_> {
-    fn render(&mut self, circus: &[u32; 2], args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
+/// 格子上短暂的闪光，渲染器通过订阅事件得知在哪里闪光
+#[derive(Debug, Default)]
+pub struct Flashes {
+    flashes: Vec<((i32, i32), f64)>, // 闪光的格子和剩余的秒数
+}
 
-        // 清空屏幕
-        clear(consts::BLACK, gl);
+impl Flashes {
+    /// 创建没有闪光的集合
+    pub fn new() -> Self {
+        Flashes::default()
+    }
 
-        // 绘制分数
-        text(
-            color::WHITE,
-            15,
-            format!("Your score is {}", self.score).as_str(),
-            &mut self.glyph_cache,
-            c.transform.trans(10.0, 20.0),
-            gl,
-        )
-            .unwrap();
+    /// 经过`dt`秒，移除已经结束的闪光
+    pub fn update(&mut self, dt: f64) {
+        for flash in self.flashes.iter_mut() {
+            flash.1 -= dt;
+        }
+        self.flashes.retain(|flash| flash.1 > 0.0);
+    }
 
-        match self.game_status {
-            GameStatus::TIMEOUT => {
-                // 绘制蛇
-                self.snake.render(&self.circus, args, gl, c);
+    /// 所有正在闪光的格子和闪光的强度，强度从1逐渐减小到0
+    pub fn iter(&self) -> impl Iterator<Item = ((i32, i32), f64)> + '_ {
+        self.flashes
+            .iter()
+            .map(|&(cell, left)| (cell, left / consts::FLASH_SECONDS))
+    }
+}
 
-                // 绘制边框
-                self.board_wall.render(&self.circus, args, gl, c);
+impl Subscriber for Flashes {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Started { .. } => self.flashes.clear(),
+            GameEvent::AteFruit { cell }
+            | GameEvent::PowerUpCollected { cell, .. }
+            | GameEvent::Collided { cell, .. } => self.flashes.push((*cell, consts::FLASH_SECONDS)),
+            _ => (),
+        }
+    }
+}
 
-                // 绘制墙壁
-                for wall in self.walls.iter_mut() {
-                    wall.render(circus, args, gl, c);
-                }
+impl Render for App<'_> {
+    fn render(&mut self, layout: &Layout, args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
+        // 记录窗口尺寸和布局，鼠标操作时需要
+        self.window_size = args.window_size;
+        self.layout = *layout;
+
+        // 清空屏幕
+        clear(self.theme.background, gl);
 
-                // 绘制食物
-                self.fruit.render(circus, args, gl, c);
+        match self.game_status {
+            GameStatus::MENU => {
+                // 绘制菜单
+                render_menu(self, args, gl, c);
+            }
+            GameStatus::TIMEOUT => {
+                // 绘制游戏画面
+                render_board(self, layout, args, gl, c);
 
                 // 绘制暂停幕布
                 let curtain = rectangle::rectangle_by_corners(
@@ -96,47 +157,506 @@ impl Render for App<'_> {
                 args.window_size[0],
                 args.window_size[1],
                 );
-                rectangle(consts::GRAY, curtain, c.transform, gl);
+                rectangle(self.theme.overlay, curtain, c.transform, gl);
 
-                // 绘制暂停信息
-                text(
-                    consts::ORANGE,
-                    15,
-                    format!("Game stop!Continue by press Enter,quit by press Esc.").as_str(),
+                // 绘制暂停菜单
+                render_menu(self, args, gl, c);
+            }
+            GameStatus::GAMING => {
+                // 绘制游戏画面
+                render_board(self, layout, args, gl, c);
+            }
+            GameStatus::RESUMING => {
+                // 绘制游戏画面和幕布
+                render_board(self, layout, args, gl, c);
+                let curtain = rectangle::rectangle_by_corners(
+                    0.0,
+                    0.0,
+                    args.window_size[0],
+                    args.window_size[1],
+                );
+                rectangle(self.theme.overlay, curtain, c.transform, gl);
+
+                // 在窗口中央绘制剩余的秒数
+                let count = format!("{}", self.countdown.ceil().max(1.0));
+                let width = self.glyph_cache.width(64, &count).unwrap_or(0.0);
+                draw_text(
+                    self.theme.highlight,
+                    64,
+                    &count,
                     &mut self.glyph_cache,
-                    c.transform.trans(10.0, 40.0),
+                    c.transform.trans(
+                        (args.window_size[0] - width) * 0.5,
+                        args.window_size[1] * 0.5,
+                    ),
                     gl,
-                )
-                .unwrap();
+                );
             }
-            GameStatus::GAMING => {
-                // 绘制蛇
-                self.snake.render(&self.circus, args, gl, c);
+            GameStatus::GAMEOVER => {
+                // 保留最后的游戏画面
+                render_board(self, layout, args, gl, c);
 
-                // 绘制边框
-                self.board_wall.render(&self.circus, args, gl, c);
+                // 绘制幕布
+                let curtain = rectangle::rectangle_by_corners(
+                0.0,
+                0.0,
+                args.window_size[0],
+                args.window_size[1],
+                );
+                rectangle(self.theme.overlay, curtain, c.transform, gl);
 
-                // 绘制墙壁
-                for wall in self.walls.iter_mut() {
-                    wall.render(&self.circus, args, gl, c);
+                // 在幕布上方用边框标出致命的碰撞
+                if let Some(death) = &self.stats.death {
+                    let rect = layout.cell_rect(death.cell.0, death.cell.1);
+                    Rectangle::new_border(self.theme.highlight, (layout.cell * 0.25).max(1.0))
+                        .draw(rect, &c.draw_state, c.transform, gl);
                 }
 
-                // 绘制食物
-                self.fruit.render(&self.circus, args, gl, c);
+                // 绘制统计数据和游戏结束菜单
+                render_menu(self, args, gl, c);
             }
-            GameStatus::GAMEOVER => {
-                // 显示游戏结束和分数
-                text(
-                    consts::RED,
-                    15,
-                    format!("Game over! Press Space to restart, Escape to quit!").as_str(),
-                    &mut self.glyph_cache,
-                    c.transform.trans(10.0, 40.0),
-                    gl,
-                )
-                .unwrap();
+            GameStatus::EDITOR => render_editor(self, layout, args, gl, c),
+            GameStatus::RESTART | GameStatus::QUIT => (),
+        }
+    }
+}
+
+/// 绘制游戏画面与分数
+fn render_board(app: &mut App, layout: &Layout, args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
+    match &app.tileset {
+        // 配置了图块集时用图块绘制
+        Some(tileset) => render_sprites(app, tileset, layout, gl, c),
+        None => {
+            // 绘制蛇
+            app.game.snake.render(layout, args, gl, c);
+
+            // 绘制边框
+            app.game.board_wall.render(layout, args, gl, c);
+
+            // 绘制墙壁
+            for wall in app.game.walls.iter_mut() {
+                wall.render(layout, args, gl, c);
+            }
+            for wall in app.game.dynamic_walls.iter_mut() {
+                wall.render(layout, args, gl, c);
+            }
+
+            // 绘制食物
+            app.game.fruit.render(layout, args, gl, c);
+
+            // 绘制道具
+            if let Some(power_up) = &mut app.game.power_up {
+                power_up.render(layout, args, gl, c);
+            }
+        }
+    }
+
+    // 传送门画在最上层，两种绘制方式相同
+    for portal in app.game.portals.iter_mut() {
+        portal.render(layout, args, gl, c);
+    }
+
+    // 吃到食物、拾取道具和撞击的格子逐渐淡出的闪光
+    for (cell, strength) in app.flashes.iter() {
+        let mut color = app.theme.highlight;
+        color[3] *= strength as f32;
+        Rectangle::new_border(color, (layout.cell * 0.2).max(1.0)).draw(
+            layout.cell_rect(cell.0, cell.1),
+            &c.draw_state,
+            c.transform,
+            gl,
+        );
+    }
+
+    // 绘制HUD
+    render_hud(app, layout, gl, c);
+}
+
+/// 绘制关卡编辑器：正在编辑的关卡、鼠标所在的格子和一行提示
+fn render_editor(app: &mut App, layout: &Layout, args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
+    let circus = app.editor.circus();
+    let map = &app.editor.map;
+
+    // 绘制边框和墙壁
+    let mut border = Wall::board_wall(&circus);
+    border.paint(app.theme.border);
+    border.render(layout, args, gl, c);
+    for wall in map.walls(&circus).iter_mut() {
+        wall.paint(app.theme.wall);
+        wall.render(layout, args, gl, c);
+    }
+
+    // 绘制食物和蛇
+    if let Some(mut fruit) = map.fruit(&circus) {
+        fruit.paint(app.theme.fruit_color(0));
+        fruit.render(layout, args, gl, c);
+    }
+    let mut snake = app.editor.snake();
+    snake.paint(&app.theme);
+    snake.render(layout, args, gl, c);
+
+    // 绘制传送门，只放置了一端的传送门使用下一对的颜色
+    let mut portals = map.portals(&circus);
+    for (i, portal) in portals.iter_mut().enumerate() {
+        portal.paint(app.theme.portal_color(i));
+        portal.render(layout, args, gl, c);
+    }
+    if let Some(start) = app.editor.portal_start {
+        let mut pending = Portal::new((start[0], start[1]), (start[0], start[1]));
+        pending.paint(app.theme.portal_color(portals.len()));
+        pending.render(layout, args, gl, c);
+    }
+
+    // 用边框标出鼠标所在的格子
+    if let Some((x, y)) = layout.cell_at(&app.cursor) {
+        Rectangle::new_border(app.theme.highlight, (layout.cell * 0.1).max(1.0)).draw(
+            layout.cell_rect(x, y),
+            &c.draw_state,
+            c.transform,
+            gl,
+        );
+    }
+
+    // 在HUD区域显示当前工具和按键提示，保存或读取之后显示结果
+    let line = match &app.editor.message {
+        Some(message) => format!("{} | {}", tr(app.editor.tool.name()), message),
+        None => trf(
+            "{} | 1-5 tools, R rotate, C clear, T test, F2 save, F3 load, Esc menu",
+            &[&tr(app.editor.tool.name())],
+        ),
+    };
+    let pos = layout.hud_item_pos(0);
+    draw_text(
+        app.theme.hud,
+        15,
+        line.as_str(),
+        &mut app.glyph_cache,
+        c.transform.trans(pos[0], pos[1] - 4.0),
+        gl,
+    );
+}
+
+/// 在HUD区域按设置中的顺序绘制各项信息
+fn render_hud(app: &mut App, layout: &Layout, gl: &mut GlGraphics, c: Context) {
+    let entries: Vec<(String, Option<[f32; 4]>)> = app
+        .settings
+        .hud
+        .iter()
+        .map(|item| (item.text(app), item.swatch(app)))
+        .collect();
+
+    for (i, (label, swatch)) in entries.iter().enumerate() {
+        let pos = layout.hud_item_pos(i);
+
+        // 文字太长时截断，避免盖住下一项和屏幕按钮
+        let swatch_width = match swatch {
+            Some(_) => consts::HUD_LINE_HEIGHT * 0.6 + 6.0,
+            None => 0.0,
+        };
+        let glyph_cache = &mut app.glyph_cache;
+        let label = hud::fit(
+            label,
+            consts::HUD_ITEM_WIDTH - consts::HUD_PADDING - swatch_width,
+            |text| glyph_cache.width(15, text).unwrap_or(0.0),
+        );
+
+        draw_text(
+            app.theme.hud,
+            15,
+            label.as_str(),
+            &mut app.glyph_cache,
+            c.transform.trans(pos[0], pos[1] - 4.0),
+            gl,
+        );
+
+        // 在文字后面绘制色块
+        if let Some(color) = swatch {
+            let width = app.glyph_cache.width(15, label.as_str()).unwrap_or(0.0);
+            let size = consts::HUD_LINE_HEIGHT * 0.6;
+            rectangle(
+                *color,
+                [pos[0] + width + 6.0, pos[1] - 4.0 - size, size, size],
+                c.transform,
+                gl,
+            );
+        }
+    }
+
+    // 在HUD项后面绘制屏幕按钮，鼠标所在的按钮高亮显示
+    for (i, button) in app.hud_buttons().iter().enumerate() {
+        let rect = layout.hud_item_rect(entries.len() + i);
+        let (background, foreground) = match app.button_at(&app.cursor) == Some(*button) {
+            true => (app.theme.highlight, app.theme.background),
+            false => (app.theme.overlay, app.theme.hud),
+        };
+
+        rectangle(background, rect, c.transform, gl);
+        draw_text(
+            foreground,
+            15,
+            tr(button.name()),
+            &mut app.glyph_cache,
+            c.transform.trans(rect[0] + 6.0, rect[1] + rect[3] - 6.0),
+            gl,
+        );
+    }
+}
+
+/// 用图块集绘制蛇、墙壁和食物，图块按实体的颜色染色
+fn render_sprites(
+    app: &App,
+    tileset: &Tileset,
+    layout: &Layout,
+    gl: &mut GlGraphics,
+    c: Context,
+) {
+    // 绘制蛇，每一段的图块取决于前后相邻的块
+    let segments: Vec<&Block> = std::iter::once(&app.game.snake.head)
+        .chain(app.game.snake.body.iter())
+        .collect();
+    for (i, block) in segments.iter().enumerate() {
+        let prev = match i {
+            0 => None,
+            _ => segments.get(i - 1).copied(),
+        };
+        let next = segments.get(i + 1).copied();
+        let (sprite, angle) = tileset::snake_sprite(block, prev, next);
+
+        tileset.draw(sprite, angle, block.color, Block::renderable_rect(layout, block), &c, gl);
+    }
+
+    // 绘制边框和墙壁
+    let bricks = app
+        .game
+        .board_wall
+        .bricks
+        .iter()
+        .chain(app.game.walls.iter().flat_map(|wall| wall.bricks.iter()))
+        .chain(app.game.dynamic_walls.iter().flat_map(|dynamic| dynamic.wall.bricks.iter()));
+    for brick in bricks {
+        tileset.draw(Sprite::Wall, 0.0, brick.color, Block::renderable_rect(layout, brick), &c, gl);
+    }
+
+    // 绘制食物
+    let fruit = &app.game.fruit.block;
+    tileset.draw(Sprite::Fruit, 0.0, fruit.color, Block::renderable_rect(layout, fruit), &c, gl);
+
+    // 绘制道具
+    if let Some(power_up) = &app.game.power_up {
+        let block = &power_up.block;
+        tileset.draw(Sprite::PowerUp, 0.0, block.color, Block::renderable_rect(layout, block), &c, gl);
+    }
+}
+
+/// 绘制当前菜单页面
+fn render_menu(app: &mut App, args: &RenderArgs, gl: &mut GlGraphics, c: Context) {
+    let window_size = args.window_size;
+
+    // 绘制页面标题
+    let title_size = match app.menu.page {
+        MenuPage::Title => 48,
+        _ => 28,
+    };
+    let title_width = app
+        .glyph_cache
+        .width(title_size, app.menu.title())
+        .unwrap_or(0.0);
+    draw_text(
+        app.theme.highlight,
+        title_size,
+        app.menu.title(),
+        &mut app.glyph_cache,
+        c.transform
+            .trans((window_size[0] - title_width) * 0.5, window_size[1] * 0.25),
+        gl,
+    );
+
+    // 游戏结束页面在菜单项上方显示统计数据
+    if app.menu.page == MenuPage::GameOver {
+        for (i, line) in game_over_lines(app).iter().enumerate() {
+            let width = app.glyph_cache.width(15, line.as_str()).unwrap_or(0.0);
+            draw_text(
+                app.theme.hud,
+                15,
+                line.as_str(),
+                &mut app.glyph_cache,
+                c.transform.trans(
+                    (window_size[0] - width) * 0.5,
+                    window_size[1] * 0.25 + 30.0 + (i as f64) * 20.0,
+                ),
+                gl,
+            );
+        }
+    }
+
+    // 暂停页面提示如何保存游戏
+    if app.menu.page == MenuPage::Pause {
+        let hint = tr("F5 to save, quitting saves automatically");
+        let width = app.glyph_cache.width(15, hint).unwrap_or(0.0);
+        draw_text(
+            app.theme.hud,
+            15,
+            hint,
+            &mut app.glyph_cache,
+            c.transform
+                .trans((window_size[0] - width) * 0.5, window_size[1] * 0.25 + 30.0),
+            gl,
+        );
+    }
+
+    // 高分榜页面在菜单项上方列出分数
+    if app.menu.page == MenuPage::HighScores {
+        let mode = app.high_score_view;
+        let table = app.high_scores.get(mode);
+        let mut scores = vec![trf("{} (Left/Right to switch)", &[&tr(mode.name())])];
+        match table.entries().len() {
+            0 => scores.push(tr("No scores yet").to_string()),
+            _ => scores.extend(
+                table
+                    .entries()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &result)| format!("{:>2}. {}", i + 1, mode.format_result(result))),
+            ),
+        }
+
+        for (i, line) in scores.iter().enumerate() {
+            draw_text(
+                app.theme.hud,
+                15,
+                line.as_str(),
+                &mut app.glyph_cache,
+                c.transform.trans(20.0, 40.0 + (i as f64) * 20.0),
+                gl,
+            );
+        }
+    }
+
+    // 绘制菜单项，被选中的菜单项高亮显示
+    for (i, item) in app.menu.items().iter().enumerate() {
+        let rect = app.menu.item_rect(i, &window_size);
+        let (background, foreground) = match i == app.menu.selected {
+            true => (app.theme.highlight, app.theme.background),
+            false => (app.theme.overlay, app.theme.hud),
+        };
+
+        // 等待新按键时提示玩家
+        let label = match (item, app.binding) {
+            (MenuAction::Setting(setting), Some(binding)) if *setting == binding => {
+                trf("{}: press a key...", &[&tr(setting.name())])
             }
-            GameStatus::RESTART => (),
+            _ => item.label(&app.settings),
+        };
+
+        rectangle(background, rect, c.transform, gl);
+        draw_text(
+            foreground,
+            18,
+            label.as_str(),
+            &mut app.glyph_cache,
+            c.transform.trans(rect[0] + 10.0, rect[1] + rect[3] - 7.0),
+            gl,
+        );
+    }
+
+    // 在窗口底部显示最近一次保存或读取的结果
+    if let Some(message) = app.menu.message.clone() {
+        let width = app.glyph_cache.width(15, &message).unwrap_or(0.0);
+        draw_text(
+            app.theme.hud,
+            15,
+            &message,
+            &mut app.glyph_cache,
+            c.transform
+                .trans((window_size[0] - width) * 0.5, window_size[1] - 20.0),
+            gl,
+        );
+    }
+}
+
+/// 游戏结束时显示的统计数据
+fn game_over_lines(app: &App) -> Vec<String> {
+    let stats = &app.stats;
+    let mode = app.mode;
+    let ending = match (&stats.death, mode) {
+        (Some(death), _) => trf("Cause of death: {}", &[&tr(death.obstacle.cause())]),
+        (None, GameMode::TimeAttack) => tr("Time's up").to_string(),
+        (None, GameMode::Target) if app.result.is_some() => trf(
+            "Reached length {} in {}",
+            &[&consts::TARGET_LENGTH, &hud::format_time(app.game.elapsed)],
+        ),
+        (None, _) => tr("Game ended").to_string(),
+    };
+    let previous = mode.format_result(stats.previous_best);
+    let best = match app.result {
+        Some(result) if result > 0 && app.high_scores.get(mode).is_better(result, stats.previous_best) => {
+            trf("New high score! Previous best {}", &[&previous])
         }
+        _ if mode.ranks_by_time() => trf("Best time {}", &[&previous]),
+        _ => trf(
+            "High score {} ({} to beat it)",
+            &[&stats.previous_best, &(stats.previous_best - app.game.score + 1)],
+        ),
+    };
+
+    let mut lines = vec![
+        trf("Score {}", &[&app.game.score]),
+        trf("Length {}", &[&app.game.snake.length()]),
+        trf("Time {}", &[&hud::format_time(app.game.elapsed)]),
+        ending,
+        trf(
+            "Cells covered {}, turns made {}",
+            &[&stats.cells_covered(), &stats.turns],
+        ),
+        best,
+    ];
+
+    // 生成的关卡显示生成器和种子，写入设置文件就能再玩一次同样的关卡
+    if app.level == Level::Generated {
+        let generator = tr(app.settings.generator.name());
+        lines.push(trf("{} seed {}", &[&generator, &app.game.seed]));
+    }
+
+    lines
+}
+
+/// 绘制一段文字，字形无法生成时跳过这段文字，不影响其他内容的绘制
+/// 每一帧都会重新绘制，所以这里不报告错误
+fn draw_text(
+    color: [f32; 4],
+    size: u32,
+    line: &str,
+    glyph_cache: &mut GlyphCache,
+    transform: math::Matrix2d,
+    gl: &mut GlGraphics,
+) {
+    text(color, size, line, glyph_cache, transform, gl).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Obstacle;
+
+    #[test]
+    fn flashes_follow_the_events_and_fade() {
+        let mut flashes = Flashes::new();
+        flashes.notify(&GameEvent::AteFruit { cell: (3, 4) });
+        flashes.notify(&GameEvent::Moved { head: (3, 4) });
+        assert_eq!(flashes.iter().collect::<Vec<_>>(), vec![((3, 4), 1.0)]);
+
+        flashes.update(consts::FLASH_SECONDS * 0.5);
+        flashes.notify(&GameEvent::Collided {
+            obstacle: Obstacle::Wall,
+            cell: (0, 0),
+        });
+        let strengths: Vec<f64> = flashes.iter().map(|flash| flash.1).collect();
+        assert_eq!(strengths, vec![0.5, 1.0]);
+
+        flashes.update(consts::FLASH_SECONDS * 0.5);
+        assert_eq!(flashes.iter().count(), 1);
+
+        flashes.notify(&GameEvent::Started { head: (5, 5) });
+        assert_eq!(flashes.iter().count(), 0);
     }
 }
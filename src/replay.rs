@@ -0,0 +1,155 @@
+use crate::{
+    event::{GameEvent, Subscriber},
+    format::{self, Format},
+    obstacle::DynamicWall,
+    Error, GameMode,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 一局游戏的录像
+/// 记录模式和初始局面，包括传送门和动态墙壁，以及每一步蛇头的位置、每个食物出现的时机
+/// 道具、新增和被摧毁的墙壁、禅模式的截断按发生的步数记录为事件
+/// 动态墙壁按步数和吃到的食物数量变化，禅模式穿过边框体现在蛇头的位置上，足以还原整局游戏
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Replay {
+    pub mode: GameMode,                  // 游戏模式
+    pub board_size: [u32; 2],            // 移动空间的尺寸
+    pub score: u32,                      // 最终分数
+    pub duration: f64,                   // 游戏时长，单位为秒
+    pub walls: Vec<[i32; 2]>,            // 开局时墙壁的所有砖块
+    pub portals: Vec<[[i32; 2]; 2]>,     // 成对的传送门
+    pub dynamic_walls: Vec<DynamicWall>, // 开局时的动态墙壁
+    pub snake: Vec<[i32; 2]>,            // 初始的蛇，第一个是蛇头
+    pub heads: Vec<[i32; 2]>,            // 每一步之后蛇头的位置
+    pub fruits: Vec<[i64; 3]>,           // 食物出现或被吸引时的步数和位置
+    pub events: Vec<(usize, GameEvent)>, // 道具、墙壁和截断事件，以及发生时的步数
+}
+
+impl Replay {
+    /// 开始录制新的一局
+    pub fn new(board_size: [u32; 2], walls: Vec<[i32; 2]>, snake: Vec<[i32; 2]>) -> Self {
+        Replay {
+            board_size,
+            walls,
+            snake,
+            ..Replay::default()
+        }
+    }
+
+    /// 记录蛇头移动到的新位置
+    pub fn record_step(&mut self, head: [i32; 2]) {
+        self.heads.push(head);
+    }
+
+    /// 记录在当前步数出现的食物
+    pub fn record_fruit(&mut self, fruit: [i32; 2]) {
+        self.fruits
+            .push([self.heads.len() as i64, fruit[0] as i64, fruit[1] as i64]);
+    }
+
+    /// 记录在当前步数发生的事件
+    pub fn record_event(&mut self, event: GameEvent) {
+        self.events.push((self.heads.len(), event));
+    }
+
+    /// 录像保存的目录
+    pub fn dir() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("snake").join("replays"))
+    }
+
+    /// 把录像保存为以当前时间命名的文件，返回文件路径
+    pub fn save(&self) -> Result<PathBuf, Error> {
+        let dir = Replay::dir().ok_or(Error::NoDataDir)?;
+        fs::create_dir_all(&dir).map_err(|e| Error::Save(dir.clone(), e))?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let path = dir
+            .join(format!("replay-{}", timestamp))
+            .with_extension(Format::Json.extension());
+        format::encode(self, Format::Json)
+            .and_then(|bytes| fs::write(&path, bytes))
+            .map_err(|e| Error::Save(path.clone(), e))?;
+
+        Ok(path)
+    }
+}
+
+impl Subscriber for Replay {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Moved { head } => self.record_step([head.0, head.1]),
+            GameEvent::FruitSpawned { cell } | GameEvent::FruitMoved { to: cell, .. } => {
+                self.record_fruit([cell.0, cell.1])
+            }
+            GameEvent::PowerUpSpawned { .. }
+            | GameEvent::PowerUpCollected { .. }
+            | GameEvent::PowerUpExpired { .. }
+            | GameEvent::WallSpawned { .. }
+            | GameEvent::WallBroken { .. }
+            | GameEvent::Trimmed { .. } => self.record_event(event.clone()),
+            GameEvent::Ended { score, elapsed } => {
+                self.score = *score;
+                self.duration = *elapsed;
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::powerup::PowerUpKind;
+
+    #[test]
+    fn events_are_recorded_with_their_step() {
+        let mut replay = Replay::new([40, 30], vec![[3, 3]], vec![[20, 15], [21, 15]]);
+        replay.notify(&GameEvent::FruitSpawned { cell: (5, 5) });
+        replay.notify(&GameEvent::Moved { head: (19, 15) });
+        replay.notify(&GameEvent::PowerUpSpawned {
+            kind: PowerUpKind::Magnet,
+            cell: (8, 8),
+        });
+        replay.notify(&GameEvent::Moved { head: (18, 15) });
+        replay.notify(&GameEvent::FruitMoved {
+            from: (5, 5),
+            to: (6, 5),
+        });
+        replay.notify(&GameEvent::WallSpawned {
+            cells: vec![(30, 3), (30, 4)],
+        });
+        replay.notify(&GameEvent::Paused);
+
+        assert_eq!(replay.heads, vec![[19, 15], [18, 15]]);
+        assert_eq!(replay.fruits, vec![[0, 5, 5], [2, 6, 5]]);
+        assert_eq!(replay.events.len(), 2);
+        assert_eq!(replay.events[0].0, 1);
+        assert_eq!(replay.events[1].0, 2);
+    }
+
+    #[test]
+    fn replays_read_back_unchanged() {
+        let mut replay = Replay {
+            mode: GameMode::Zen,
+            portals: vec![[[2, 2], [9, 9]]],
+            ..Replay::new([40, 30], Vec::new(), vec![[20, 15]])
+        };
+        replay.notify(&GameEvent::Trimmed {
+            cell: (20, 16),
+            length: 3,
+        });
+        let bytes = format::encode(&replay, Format::Json).unwrap();
+
+        assert_eq!(
+            format::decode::<Replay>(&bytes, Format::Json).unwrap(),
+            replay
+        );
+    }
+}
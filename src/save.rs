@@ -0,0 +1,121 @@
+use crate::{
+    format::{self, Format},
+    game::Game,
+    level::Level,
+    replay::Replay,
+    stats::GameStats,
+    Error,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// 存档使用的格式
+const SAVE_FORMAT: Format = Format::Binary;
+
+/// 保存下来的一局游戏，包含继续游戏所需的全部状态
+/// 颜色在读取后按当前主题重新上色
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub level: Level,     // 关卡
+    pub game: Game,       // 这一局的局面和随机数生成器的状态
+    pub stats: GameStats, // 统计数据
+    pub replay: Replay,   // 到目前为止的录像
+}
+
+impl SavedGame {
+    /// 存档文件的路径
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| {
+            dir.join("snake")
+                .join("save")
+                .with_extension(SAVE_FORMAT.extension())
+        })
+    }
+
+    /// 是否有可以继续的存档
+    pub fn exists() -> bool {
+        SavedGame::path().is_some_and(|path| path.is_file())
+    }
+
+    /// 写入存档文件，返回文件路径
+    pub fn save(&self) -> Result<PathBuf, Error> {
+        let path = SavedGame::path().ok_or(Error::NoDataDir)?;
+
+        let write = || -> io::Result<()> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, format::encode(self, SAVE_FORMAT)?)
+        };
+        write().map_err(|e| Error::Save(path.clone(), e))?;
+
+        Ok(path)
+    }
+
+    /// 读取存档文件
+    pub fn load() -> Result<Self, Error> {
+        let path = SavedGame::path().ok_or(Error::NoDataDir)?;
+
+        fs::read(&path)
+            .and_then(|bytes| format::decode(&bytes, SAVE_FORMAT))
+            .map_err(|e| Error::Save(path, e))
+    }
+
+    /// 删除存档文件，继续游戏之后存档就失效了
+    pub fn remove() -> Result<(), Error> {
+        match SavedGame::path() {
+            Some(path) if path.is_file() => {
+                fs::remove_file(&path).map_err(|e| Error::Save(path, e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{obstacle::Portal, powerup::PowerUpKind, GameMode};
+
+    // 一局进行到一半的游戏，各种状态都不为空
+    fn saved_game() -> SavedGame {
+        let mut game = Game::new(GameMode::Survival, [40, 30], 7);
+        game.dynamic_walls = Level::Sliding.dynamic_walls(&game.circus);
+        game.portals = vec![Portal::new((3, 3), (30, 20))];
+        game.effects.activate(PowerUpKind::SlowMotion);
+        for _ in 0..20 {
+            game.update(1.0 / game.velocity());
+        }
+
+        SavedGame {
+            level: Level::Sliding,
+            game,
+            stats: GameStats::new(),
+            replay: Replay::new([40, 30], vec![[5, 5]], vec![[20, 15]]),
+        }
+    }
+
+    #[test]
+    fn saved_games_read_back_unchanged() {
+        let saved = saved_game();
+        let bytes = format::encode(&saved, SAVE_FORMAT).unwrap();
+        let loaded: SavedGame = format::decode(&bytes, SAVE_FORMAT).unwrap();
+
+        assert_eq!(loaded.game.mode, saved.game.mode);
+        assert_eq!(loaded.level, saved.level);
+        assert_eq!(loaded.game.snake, saved.game.snake);
+        assert_eq!(loaded.game.portals, saved.game.portals);
+        // 再次编码得到相同的内容，说明没有状态在读写中丢失
+        assert_eq!(format::encode(&loaded, SAVE_FORMAT).unwrap(), bytes);
+    }
+
+    #[test]
+    fn saves_from_other_versions_are_rejected() {
+        let mut bytes = format::encode(&saved_game(), SAVE_FORMAT).unwrap();
+        bytes[0] = bytes[0].wrapping_add(1);
+
+        assert!(format::decode::<SavedGame>(&bytes, SAVE_FORMAT).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{consts, GameMode};
+#[cfg(feature = "serde")]
+use crate::{
+    format::{self, Format},
+    Error,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::{fs, io, path::PathBuf};
+
+/// 高分榜文件使用的格式
+#[cfg(feature = "serde")]
+const SCORES_FORMAT: Format = Format::Json;
+
+/// 高分榜
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HighScores {
+    entries: Vec<u32>,
+    ascending: bool, // 数值越小越好，用于记录用时
+}
+
+impl HighScores {
+    /// 创建空的高分榜
+    pub fn new() -> Self {
+        HighScores {
+            entries: Vec::new(),
+            ascending: false,
+        }
+    }
+
+    /// 创建数值越小越好的空高分榜
+    pub fn ascending() -> Self {
+        HighScores {
+            entries: Vec::new(),
+            ascending: true,
+        }
+    }
+
+    /// `value`是否比`other`更好，0表示还没有成绩
+    pub fn is_better(&self, value: u32, other: u32) -> bool {
+        match (other, self.ascending) {
+            (0, _) => true,
+            (_, true) => value < other,
+            (_, false) => value > other,
+        }
+    }
+
+    /// 记录一次分数，进入榜单时返回true
+    pub fn insert(&mut self, score: u32) -> bool {
+        if score == 0 {
+            return false;
+        }
+
+        let pos = self
+            .entries
+            .iter()
+            .position(|&entry| self.is_better(score, entry))
+            .unwrap_or(self.entries.len());
+
+        if pos >= consts::MAX_HIGH_SCORES {
+            return false;
+        }
+
+        self.entries.insert(pos, score);
+        self.entries.truncate(consts::MAX_HIGH_SCORES);
+
+        true
+    }
+
+    /// 最好的成绩
+    pub fn best(&self) -> u32 {
+        self.entries.first().copied().unwrap_or(0)
+    }
+
+    /// 按从高到低排列的所有分数
+    pub fn entries(&self) -> &[u32] {
+        &self.entries
+    }
+}
+
+/// 每种游戏模式各自的高分榜
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScoreTables {
+    tables: Vec<HighScores>, // 按`GameMode::ALL`的顺序排列
+}
+
+impl Default for ScoreTables {
+    fn default() -> Self {
+        ScoreTables::new()
+    }
+}
+
+impl ScoreTables {
+    /// 为每种模式创建空的高分榜
+    pub fn new() -> Self {
+        let tables = GameMode::ALL
+            .iter()
+            .map(|mode| match mode.ranks_by_time() {
+                true => HighScores::ascending(),
+                false => HighScores::new(),
+            })
+            .collect();
+
+        ScoreTables { tables }
+    }
+
+    /// 模式的高分榜
+    pub fn get(&self, mode: GameMode) -> &HighScores {
+        &self.tables[ScoreTables::index(mode)]
+    }
+
+    /// 模式的高分榜，用于记录成绩
+    pub fn get_mut(&mut self, mode: GameMode) -> &mut HighScores {
+        &mut self.tables[ScoreTables::index(mode)]
+    }
+
+    fn index(mode: GameMode) -> usize {
+        GameMode::ALL
+            .iter()
+            .position(|&other| other == mode)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ScoreTables {
+    /// 高分榜文件的路径
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| {
+            dir.join("snake")
+                .join("scores")
+                .with_extension(SCORES_FORMAT.extension())
+        })
+    }
+
+    /// 读取高分榜文件，文件不存在时返回空的高分榜
+    pub fn load() -> Result<Self, Error> {
+        let path = match ScoreTables::path() {
+            Some(path) if path.is_file() => path,
+            _ => return Ok(ScoreTables::new()),
+        };
+
+        fs::read(&path)
+            .and_then(|bytes| ScoreTables::decode(&bytes))
+            .map_err(|e| Error::Save(path, e))
+    }
+
+    /// 写入高分榜文件，返回文件路径
+    pub fn save(&self) -> Result<PathBuf, Error> {
+        let path = ScoreTables::path().ok_or(Error::NoDataDir)?;
+
+        let write = || -> io::Result<()> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, format::encode(self, SCORES_FORMAT)?)
+        };
+        write().map_err(|e| Error::Save(path.clone(), e))?;
+
+        Ok(path)
+    }
+
+    // 解析高分榜文件的内容
+    fn decode(bytes: &[u8]) -> io::Result<Self> {
+        format::decode::<ScoreTables>(bytes, SCORES_FORMAT).map(ScoreTables::repair)
+    }
+
+    // 文件中的高分榜可能来自模式数量不同的版本，也可能被手动修改过
+    // 按模式补齐榜单，排序方向以模式为准，重新插入分数去掉无效和多余的成绩
+    fn repair(self) -> Self {
+        let mut tables = ScoreTables::new();
+        for (table, saved) in tables.tables.iter_mut().zip(self.tables) {
+            for &score in saved.entries() {
+                table.insert(score);
+            }
+        }
+
+        tables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_keep_the_best_scores_in_order() {
+        let mut tables = ScoreTables::new();
+        for score in [3, 0, 9, 5] {
+            tables.get_mut(GameMode::Classic).insert(score);
+        }
+        let target = GameMode::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.ranks_by_time())
+            .unwrap();
+        tables.get_mut(target).insert(300);
+        tables.get_mut(target).insert(120);
+
+        assert_eq!(tables.get(GameMode::Classic).entries(), &[9, 5, 3]);
+        assert_eq!(tables.get(target).best(), 120);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn saved_tables_read_back_unchanged() {
+        let mut tables = ScoreTables::new();
+        tables.get_mut(GameMode::Classic).insert(42);
+        let bytes = format::encode(&tables, SCORES_FORMAT).unwrap();
+
+        assert_eq!(ScoreTables::decode(&bytes).unwrap(), tables);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn edited_tables_are_repaired() {
+        // 只有一个模式的榜单，顺序和数量都不对
+        let saved = ScoreTables {
+            tables: vec![HighScores {
+                entries: vec![0; 3].into_iter().chain(1..=20).collect(),
+                ascending: true,
+            }],
+        };
+        let bytes = format::encode(&saved, SCORES_FORMAT).unwrap();
+        let tables = ScoreTables::decode(&bytes).unwrap();
+
+        let classic = tables.get(GameMode::Classic).entries();
+        assert_eq!(classic.len(), consts::MAX_HIGH_SCORES);
+        assert_eq!(classic[0], 20);
+        for &mode in GameMode::ALL.iter().skip(1) {
+            assert!(tables.get(mode).entries().is_empty());
+        }
+    }
+}
@@ -0,0 +1,657 @@
+use crate::{
+    consts,
+    gamepad::PadButton,
+    generator::Generator,
+    hud::HudItem,
+    locale::{tr, Language},
+    Error,
+};
+use piston::input::Key;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// 随机墙壁的密度
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum WallDensity {
+    None,
+    Low,
+    Normal,
+    High,
+}
+
+impl WallDensity {
+    const ALL: [WallDensity; 4] = [
+        WallDensity::None,
+        WallDensity::Low,
+        WallDensity::Normal,
+        WallDensity::High,
+    ];
+
+    /// 密度名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            WallDensity::None => "None",
+            WallDensity::Low => "Low",
+            WallDensity::Normal => "Normal",
+            WallDensity::High => "High",
+        }
+    }
+
+    /// 随机墙壁数量的范围，左闭右开
+    pub fn range(&self) -> (u32, u32) {
+        match self {
+            WallDensity::None => (0, 0),
+            WallDensity::Low => (1, 4),
+            WallDensity::Normal => (1, 10),
+            WallDensity::High => (8, 16),
+        }
+    }
+}
+
+/// 按键绑定
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub up: Key,
+    pub down: Key,
+    pub left: Key,
+    pub right: Key,
+    pub pause: Key,
+    pub mute: Key,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            up: Key::Up,
+            down: Key::Down,
+            left: Key::Left,
+            right: Key::Right,
+            pause: Key::Space,
+            mute: Key::M,
+        }
+    }
+}
+
+/// 分配给一名玩家的手柄
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerPad {
+    pub controller: Option<usize>, // 手柄编号，为空时没有指定手柄
+}
+
+/// 手柄的分配、按钮映射和摇杆设置
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadSettings {
+    pub deadzone: f64,           // 摇杆的死区，范围是0.0到1.0
+    pub start: PadButton,        // 暂停或继续的按钮
+    pub confirm: PadButton,      // 选择菜单项的按钮
+    pub back: PadButton,         // 返回上一级菜单的按钮
+    pub players: Vec<PlayerPad>, // 每名玩家的手柄，一个手柄只分配给一名玩家
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        GamepadSettings {
+            deadzone: 0.3,
+            start: PadButton::Start,
+            confirm: PadButton::South,
+            back: PadButton::East,
+            players: vec![PlayerPad::default(); consts::MAX_PLAYERS],
+        }
+    }
+}
+
+impl GamepadSettings {
+    /// `id`号手柄分配给的玩家，从0开始
+    /// 玩家1没有指定手柄时接受其他玩家没有占用的任何手柄，其他玩家必须指定手柄
+    pub fn player(&self, id: usize) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|pad| pad.controller == Some(id))
+            .or_else(|| match self.players.first() {
+                Some(pad) if pad.controller.is_none() => Some(0),
+                _ => None,
+            })
+    }
+
+    /// 为玩家指定的手柄
+    pub fn controller(&self, player: usize) -> Option<usize> {
+        self.players.get(player).and_then(|pad| pad.controller)
+    }
+
+    /// 把手柄分配给玩家，手柄已经分配给其他玩家时两者交换
+    pub fn assign(&mut self, player: usize, controller: Option<usize>) {
+        let old = match self.players.get_mut(player) {
+            Some(pad) => std::mem::replace(&mut pad.controller, controller),
+            None => return,
+        };
+
+        for (other, pad) in self.players.iter_mut().enumerate() {
+            if other != player && controller.is_some() && pad.controller == controller {
+                pad.controller = old;
+            }
+        }
+    }
+}
+
+/// 玩家的设置，启动时从设置文件读取
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub board_size: [u32; 2],      // 移动空间的尺寸，下一局生效
+    pub speed: f64,                // 蛇的初始速度，下一局生效
+    pub wall_density: WallDensity, // 随机墙壁的密度，下一局生效
+    pub generator: Generator,      // 生成关卡使用的生成器，下一局生效
+    pub seed: Option<u64>,         // 生成关卡使用的种子，为空时每局随机
+    pub volume: f32,               // 音效音量，范围是0.0到1.0
+    pub music_volume: f32,         // 背景音乐音量，范围是0.0到1.0
+    pub muted: bool,               // 是否静音
+    pub fullscreen: bool,          // 是否全屏，也可以按F11切换
+    pub theme: String,             // 配色主题的名称
+    pub language: Language,        // 界面语言
+    pub tileset: Option<String>,   // 图块集图片的路径，为空时用矩形绘制
+    pub hud: Vec<HudItem>,         // HUD中显示的信息
+    pub mouse_control: bool,       // 是否可以用鼠标点击和滑动控制方向，并显示屏幕按钮
+    pub keys: KeyBindings,         // 按键绑定
+    pub gamepad: GamepadSettings,  // 手柄设置
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            board_size: [80, 60],
+            speed: 6.0,
+            wall_density: WallDensity::Normal,
+            generator: Generator::Rooms,
+            seed: None,
+            volume: 0.8,
+            music_volume: 0.5,
+            muted: false,
+            fullscreen: false,
+            theme: "Classic".to_string(),
+            language: Language::Auto,
+            tileset: None,
+            hud: HudItem::ALL.to_vec(),
+            mouse_control: true,
+            keys: KeyBindings::default(),
+            gamepad: GamepadSettings::default(),
+        }
+    }
+}
+
+/// 每名玩家的手柄设置项名称
+const CONTROLLER_NAMES: [&str; consts::MAX_PLAYERS] =
+    ["Player 1 controller", "Player 2 controller"];
+
+/// 选项菜单中可以调整的设置项
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Setting {
+    BoardSize,
+    Speed,
+    WallDensity,
+    Generator,
+    Seed,
+    Theme,
+    Language,
+    Sprites,
+    Hud(HudItem),
+    Fullscreen,
+    Volume,
+    MusicVolume,
+    Mute,
+    KeyUp,
+    KeyDown,
+    KeyLeft,
+    KeyRight,
+    KeyPause,
+    KeyMute,
+    MouseControl,
+    Controller(usize),
+    Deadzone,
+}
+
+impl Setting {
+    /// 设置项名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Setting::BoardSize => "Board size",
+            Setting::Speed => "Speed",
+            Setting::WallDensity => "Wall density",
+            Setting::Generator => "Generator",
+            Setting::Seed => "Seed",
+            Setting::Theme => "Theme",
+            Setting::Language => "Language",
+            Setting::Sprites => "Sprites",
+            Setting::Hud(item) => item.name(),
+            Setting::Fullscreen => "Fullscreen",
+            Setting::Volume => "Effects volume",
+            Setting::MusicVolume => "Music volume",
+            Setting::Mute => "Mute",
+            Setting::KeyUp => "Up",
+            Setting::KeyDown => "Down",
+            Setting::KeyLeft => "Left",
+            Setting::KeyRight => "Right",
+            Setting::KeyPause => "Pause",
+            Setting::KeyMute => "Mute",
+            Setting::MouseControl => "Mouse control",
+            Setting::Controller(player) => CONTROLLER_NAMES
+                .get(*player)
+                .copied()
+                .unwrap_or("Controller"),
+            Setting::Deadzone => "Stick deadzone",
+        }
+    }
+
+    /// 所有按键绑定
+    pub const KEYS: [Setting; 6] = [
+        Setting::KeyUp,
+        Setting::KeyDown,
+        Setting::KeyLeft,
+        Setting::KeyRight,
+        Setting::KeyPause,
+        Setting::KeyMute,
+    ];
+
+    /// 是否为按键绑定
+    pub fn is_key(&self) -> bool {
+        Setting::KEYS.contains(self)
+    }
+}
+
+impl Settings {
+    /// 设置文件的位置
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("snake").join("settings.toml"))
+    }
+
+    /// 读取设置文件，文件不存在时使用默认设置，无法解析时返回错误
+    pub fn load() -> Result<Self, Error> {
+        let path = match Settings::path() {
+            Some(path) => path,
+            None => return Ok(Settings::default()),
+        };
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => return Ok(Settings::default()),
+        };
+
+        let mut settings: Settings =
+            toml::from_str(&content).map_err(|e| Error::Config(path, e.to_string()))?;
+        settings.sanitize();
+
+        Ok(settings)
+    }
+
+    // 手动修改的设置文件可能包含无法使用的值，把它们限制到选项菜单中可以选择的范围
+    fn sanitize(&mut self) {
+        // 不在可选尺寸中时使用面积最接近的尺寸
+        if !consts::BOARD_SIZES.contains(&self.board_size) {
+            let area = |size: &[u32; 2]| size[0] as u64 * size[1] as u64;
+            let current = area(&self.board_size);
+            self.board_size = *consts::BOARD_SIZES
+                .iter()
+                .min_by_key(|size| area(size).abs_diff(current))
+                .unwrap_or(&consts::BOARD_SIZES[0]);
+        }
+
+        let default = Settings::default();
+        self.speed = limit(
+            self.speed,
+            consts::MIN_SPEED,
+            consts::MAX_SPEED,
+            default.speed,
+        );
+        self.volume = limit(self.volume, 0.0, 1.0, default.volume);
+        self.music_volume = limit(self.music_volume, 0.0, 1.0, default.music_volume);
+        self.gamepad.deadzone = limit(
+            self.gamepad.deadzone,
+            consts::MIN_DEADZONE,
+            consts::MAX_DEADZONE,
+            default.gamepad.deadzone,
+        );
+
+        // 每名玩家一项，去掉超出范围和重复分配的手柄
+        self.gamepad
+            .players
+            .resize(consts::MAX_PLAYERS, PlayerPad::default());
+        for player in 0..consts::MAX_PLAYERS {
+            let controller = self.gamepad.players[player].controller;
+            let taken = self.gamepad.players[..player]
+                .iter()
+                .any(|pad| pad.controller.is_some() && pad.controller == controller);
+            if taken || controller.is_some_and(|id| id >= consts::MAX_CONTROLLERS) {
+                self.gamepad.players[player].controller = None;
+            }
+        }
+    }
+
+    /// 把设置写入设置文件
+    pub fn save(&self) -> io::Result<()> {
+        let path = Settings::path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        fs::write(path, content)
+    }
+
+    /// 设置项当前的值，按界面语言显示
+    pub fn value(&self, setting: Setting) -> String {
+        match setting {
+            Setting::BoardSize => format!("{} x {}", self.board_size[0], self.board_size[1]),
+            Setting::Speed => format!("{}", self.speed),
+            Setting::WallDensity => tr(self.wall_density.name()).to_string(),
+            Setting::Generator => tr(self.generator.name()).to_string(),
+            Setting::Seed => match self.seed {
+                Some(seed) => seed.to_string(),
+                None => tr("Random").to_string(),
+            },
+            Setting::Theme => self.theme.clone(),
+            Setting::Language => tr(self.language.name()).to_string(),
+            Setting::Sprites => match self.tileset {
+                Some(_) => tr("On").to_string(),
+                None => tr("Off").to_string(),
+            },
+            Setting::Hud(item) => match self.hud.contains(&item) {
+                true => tr("On").to_string(),
+                false => tr("Off").to_string(),
+            },
+            Setting::Fullscreen => match self.fullscreen {
+                true => tr("On").to_string(),
+                false => tr("Off").to_string(),
+            },
+            Setting::Volume => format!("{}%", (self.volume * 100.0).round()),
+            Setting::MusicVolume => format!("{}%", (self.music_volume * 100.0).round()),
+            Setting::Mute => match self.muted {
+                true => tr("On").to_string(),
+                false => tr("Off").to_string(),
+            },
+            Setting::KeyUp => format!("{:?}", self.keys.up),
+            Setting::KeyDown => format!("{:?}", self.keys.down),
+            Setting::KeyLeft => format!("{:?}", self.keys.left),
+            Setting::KeyRight => format!("{:?}", self.keys.right),
+            Setting::KeyPause => format!("{:?}", self.keys.pause),
+            Setting::KeyMute => format!("{:?}", self.keys.mute),
+            Setting::MouseControl => match self.mouse_control {
+                true => tr("On").to_string(),
+                false => tr("Off").to_string(),
+            },
+            // 手柄编号从1开始显示
+            Setting::Controller(player) => match self.gamepad.controller(player) {
+                Some(id) => format!("#{}", id + 1),
+                None if player == 0 => tr("Any").to_string(),
+                None => tr("None").to_string(),
+            },
+            Setting::Deadzone => format!("{}%", (self.gamepad.deadzone * 100.0).round()),
+        }
+    }
+
+    /// 把设置项向前或向后调整`step`档，主题由`Themes`负责切换
+    pub fn adjust(&mut self, setting: Setting, step: i32) {
+        match setting {
+            Setting::BoardSize => {
+                let index = cycle(&consts::BOARD_SIZES, &self.board_size, step);
+                self.board_size = consts::BOARD_SIZES[index];
+            }
+            Setting::Speed => {
+                self.speed = (self.speed + step as f64).clamp(consts::MIN_SPEED, consts::MAX_SPEED);
+            }
+            Setting::WallDensity => {
+                let index = cycle(&WallDensity::ALL, &self.wall_density, step);
+                self.wall_density = WallDensity::ALL[index];
+            }
+            Setting::Generator => {
+                let index = cycle(&Generator::ALL, &self.generator, step);
+                self.generator = Generator::ALL[index];
+            }
+            // 固定的种子逐个调整，越过0时恢复为每局随机
+            Setting::Seed => {
+                self.seed = self
+                    .seed
+                    .and_then(|seed| seed.checked_add_signed(step as i64));
+            }
+            Setting::Language => {
+                let index = cycle(&Language::ALL, &self.language, step);
+                self.language = Language::ALL[index];
+            }
+            Setting::Sprites => {
+                self.tileset = match self.tileset {
+                    Some(_) => None,
+                    None => Some(consts::DEFAULT_TILESET.to_string()),
+                }
+            }
+            Setting::Hud(item) => {
+                // 保持HUD项的固定顺序
+                let shown = !self.hud.contains(&item);
+                let hud = &self.hud;
+                self.hud = HudItem::ALL
+                    .iter()
+                    .filter(|&&other| match other == item {
+                        true => shown,
+                        false => hud.contains(&other),
+                    })
+                    .copied()
+                    .collect();
+            }
+            Setting::Fullscreen => self.fullscreen = !self.fullscreen,
+            Setting::Volume => {
+                self.volume = (self.volume + 0.1 * step as f32).clamp(0.0, 1.0);
+            }
+            Setting::MusicVolume => {
+                self.music_volume = (self.music_volume + 0.1 * step as f32).clamp(0.0, 1.0);
+            }
+            Setting::Mute => self.muted = !self.muted,
+            Setting::MouseControl => self.mouse_control = !self.mouse_control,
+            Setting::Controller(player) => {
+                let options: Vec<Option<usize>> = std::iter::once(None)
+                    .chain((0..consts::MAX_CONTROLLERS).map(Some))
+                    .collect();
+                let index = cycle(&options, &self.gamepad.controller(player), step);
+                self.gamepad.assign(player, options[index]);
+            }
+            Setting::Deadzone => {
+                self.gamepad.deadzone = (self.gamepad.deadzone + 0.1 * step as f64)
+                    .clamp(consts::MIN_DEADZONE, consts::MAX_DEADZONE);
+            }
+            _ => (),
+        }
+    }
+
+    /// 把按键绑定到设置项上，按键已经绑定到其他设置项时两者交换
+    /// F11和Esc有固定的用途，不能绑定，这时返回false
+    pub fn bind(&mut self, setting: Setting, key: Key) -> bool {
+        if key == Key::F11 || key == Key::Escape {
+            return false;
+        }
+        let old = match self.key_mut(setting) {
+            Some(bound) => std::mem::replace(bound, key),
+            None => return false,
+        };
+
+        for &other in Setting::KEYS.iter().filter(|&&other| other != setting) {
+            if let Some(bound) = self.key_mut(other).filter(|bound| **bound == key) {
+                *bound = old;
+            }
+        }
+
+        true
+    }
+
+    // 设置项对应的按键
+    fn key_mut(&mut self, setting: Setting) -> Option<&mut Key> {
+        match setting {
+            Setting::KeyUp => Some(&mut self.keys.up),
+            Setting::KeyDown => Some(&mut self.keys.down),
+            Setting::KeyLeft => Some(&mut self.keys.left),
+            Setting::KeyRight => Some(&mut self.keys.right),
+            Setting::KeyPause => Some(&mut self.keys.pause),
+            Setting::KeyMute => Some(&mut self.keys.mute),
+            _ => None,
+        }
+    }
+}
+
+/// 把`value`限制在`min`到`max`之间，不是数字时使用`fallback`
+fn limit<T: PartialOrd>(value: T, min: T, max: T, fallback: T) -> T {
+    if value < min {
+        min
+    } else if value > max {
+        max
+    } else if value >= min {
+        value
+    } else {
+        fallback
+    }
+}
+
+/// 在`options`中找到`current`，返回移动`step`格后的下标
+fn cycle<T: PartialEq>(options: &[T], current: &T, step: i32) -> usize {
+    let len = options.len() as i32;
+    let index = options.iter().position(|option| option == current).unwrap_or(0) as i32;
+
+    ((index + step) % len + len) as usize % options.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 像读取设置文件一样解析并修正设置
+    fn parse(content: &str) -> Settings {
+        let mut settings: Settings = toml::from_str(content).unwrap();
+        settings.sanitize();
+        settings
+    }
+
+    #[test]
+    fn unusable_values_are_limited() {
+        let settings = parse(
+            "board_size = [2, 2]\n\
+             speed = 0.0\n\
+             volume = 3.0\n\
+             music_volume = -1.0\n\
+             [gamepad]\n\
+             deadzone = nan\n",
+        );
+
+        assert_eq!(settings.board_size, consts::BOARD_SIZES[0]);
+        assert_eq!(settings.speed, consts::MIN_SPEED);
+        assert_eq!(settings.volume, 1.0);
+        assert_eq!(settings.music_volume, 0.0);
+        assert_eq!(settings.gamepad.deadzone, 0.3);
+    }
+
+    #[test]
+    fn board_size_snaps_to_the_closest_choice() {
+        assert_eq!(parse("board_size = [1000, 1000]").board_size, [100, 75]);
+        assert_eq!(parse("board_size = [62, 44]").board_size, [60, 45]);
+        assert_eq!(parse("board_size = [0, 60]").board_size, [40, 30]);
+    }
+
+    #[test]
+    fn valid_values_are_kept() {
+        let settings = parse("board_size = [60, 45]\nspeed = 12.0\nvolume = 0.5");
+
+        assert_eq!(settings.board_size, [60, 45]);
+        assert_eq!(settings.speed, 12.0);
+        assert_eq!(settings.volume, 0.5);
+        assert_eq!(parse(""), Settings::default());
+    }
+
+    #[test]
+    fn saved_settings_read_back_unchanged() {
+        let settings = Settings {
+            mouse_control: false,
+            seed: Some(7),
+            ..Settings::default()
+        };
+        let content = toml::to_string_pretty(&settings).unwrap();
+
+        assert_eq!(parse(&content), settings);
+    }
+
+    #[test]
+    fn controllers_are_assigned_per_player() {
+        let mut gamepad = GamepadSettings::default();
+        assert_eq!(gamepad.player(3), Some(0));
+
+        // 玩家2占用的手柄不再归玩家1
+        gamepad.assign(1, Some(2));
+        assert_eq!(gamepad.player(2), Some(1));
+        assert_eq!(gamepad.player(0), Some(0));
+
+        // 玩家1指定手柄后，没有分配的手柄不属于任何玩家
+        gamepad.assign(0, Some(0));
+        assert_eq!(gamepad.player(0), Some(0));
+        assert_eq!(gamepad.player(3), None);
+
+        // 分配已经占用的手柄时两名玩家交换
+        gamepad.assign(0, Some(2));
+        assert_eq!(gamepad.player(2), Some(0));
+        assert_eq!(gamepad.player(0), Some(1));
+    }
+
+    #[test]
+    fn controller_assignments_survive_the_settings_file() {
+        let mut settings = Settings::default();
+        settings.gamepad.assign(1, Some(3));
+        let content = toml::to_string_pretty(&settings).unwrap();
+        assert_eq!(parse(&content), settings);
+
+        // 超出范围或重复的分配被清除，缺少的玩家补上
+        let settings = parse(
+            "[[gamepad.players]]\n\
+             controller = 1\n\
+             [[gamepad.players]]\n\
+             controller = 1\n\
+             [[gamepad.players]]\n\
+             controller = 9\n",
+        );
+        assert_eq!(
+            settings.gamepad.players,
+            vec![
+                PlayerPad {
+                    controller: Some(1)
+                },
+                PlayerPad { controller: None },
+            ]
+        );
+        assert_eq!(parse("").gamepad.players.len(), consts::MAX_PLAYERS);
+    }
+
+    #[test]
+    fn binding_a_taken_key_swaps_the_two() {
+        let mut settings = Settings::default();
+
+        assert!(settings.bind(Setting::KeyUp, Key::M));
+        assert_eq!(settings.keys.up, Key::M);
+        assert_eq!(settings.keys.mute, Key::Up);
+
+        assert!(settings.bind(Setting::KeyPause, Key::Left));
+        assert_eq!(settings.keys.pause, Key::Left);
+        assert_eq!(settings.keys.left, Key::Space);
+
+        assert!(settings.bind(Setting::KeyDown, Key::S));
+        assert_eq!(settings.keys.down, Key::S);
+    }
+
+    #[test]
+    fn reserved_keys_cannot_be_bound() {
+        let mut settings = Settings::default();
+
+        assert!(!settings.bind(Setting::KeyUp, Key::F11));
+        assert!(!settings.bind(Setting::KeyPause, Key::Escape));
+        assert!(!settings.bind(Setting::Speed, Key::Q));
+        assert_eq!(settings.keys, KeyBindings::default());
+    }
+}
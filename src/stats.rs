@@ -0,0 +1,52 @@
+use crate::event::{GameEvent, Obstacle, Subscriber};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 导致游戏结束的碰撞
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Death {
+    pub obstacle: Obstacle, // 撞上的障碍物
+    pub cell: (i32, i32),   // 发生碰撞的格子
+}
+
+/// 一局游戏的统计数据
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GameStats {
+    pub turns: u32,                   // 转向的次数
+    pub visited: HashSet<(i32, i32)>, // 蛇头经过的格子
+    pub death: Option<Death>,         // 死亡原因
+    pub previous_best: u32,           // 本局之前的最高分
+}
+
+impl GameStats {
+    /// 创建空的统计数据
+    pub fn new() -> Self {
+        GameStats::default()
+    }
+
+    /// 蛇头经过的格子数
+    pub fn cells_covered(&self) -> usize {
+        self.visited.len()
+    }
+}
+
+impl Subscriber for GameStats {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Started { head } | GameEvent::Moved { head } => {
+                self.visited.insert(*head);
+            }
+            GameEvent::Turned { .. } => self.turns += 1,
+            GameEvent::Collided { obstacle, cell } => {
+                self.death = Some(Death {
+                    obstacle: *obstacle,
+                    cell: *cell,
+                })
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::powerup::PowerUpKind;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// 内置的主题文件
+const BUILTIN_THEMES: [&str; 4] = [
+    include_str!("../assets/themes/classic.toml"),
+    include_str!("../assets/themes/high_contrast.toml"),
+    include_str!("../assets/themes/colorblind.toml"),
+    include_str!("../assets/themes/retro.toml"),
+];
+
+/// 配色主题
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,          // 主题名称
+    pub background: [f32; 4],  // 背景
+    pub border: [f32; 4],      // 边框
+    pub wall: [f32; 4],        // 墙壁
+    pub head: [f32; 4],        // 蛇头
+    pub body_start: [f32; 4],  // 紧挨蛇头的蛇身
+    pub body_end: [f32; 4],    // 蛇尾，中间的蛇身按渐变上色
+    pub fruits: Vec<[f32; 4]>, // 各种食物的颜色
+    #[serde(default)]
+    pub power_ups: Vec<[f32; 4]>, // 各种道具的颜色，按`PowerUpKind::ALL`的顺序
+    #[serde(default)]
+    pub portals: Vec<[f32; 4]>, // 各对传送门的颜色，同一对的两端颜色相同
+    pub hud: [f32; 4],         // 分数等文字
+    pub highlight: [f32; 4],   // 标题和被选中的菜单项
+    pub overlay: [f32; 4],     // 暂停幕布和菜单项背景
+}
+
+impl Theme {
+    /// 蛇身第`index`块的颜色，`len`是蛇身的长度
+    pub fn body_color(&self, index: usize, len: usize) -> [f32; 4] {
+        let t = match len {
+            0 | 1 => 0.0,
+            _ => index as f32 / (len - 1) as f32,
+        };
+
+        let mut color = [0.0; 4];
+        for (i, c) in color.iter_mut().enumerate() {
+            *c = self.body_start[i] + (self.body_end[i] - self.body_start[i]) * t;
+        }
+
+        color
+    }
+
+    /// 第`index`种食物的颜色
+    pub fn fruit_color(&self, index: usize) -> [f32; 4] {
+        match self.fruits.len() {
+            0 => self.head,
+            len => self.fruits[index % len],
+        }
+    }
+
+    /// 第`index`对传送门的颜色，主题没有配置传送门颜色时使用高亮色
+    pub fn portal_color(&self, index: usize) -> [f32; 4] {
+        match self.portals.len() {
+            0 => self.highlight,
+            len => self.portals[index % len],
+        }
+    }
+
+    /// 道具的颜色，主题没有配置道具颜色时使用高亮色
+    pub fn power_up_color(&self, kind: PowerUpKind) -> [f32; 4] {
+        match self.power_ups.len() {
+            0 => self.highlight,
+            len => self.power_ups[kind.index() % len],
+        }
+    }
+}
+
+/// 所有可用的主题，包括内置主题和玩家放在配置目录中的主题
+#[derive(Debug, Clone)]
+pub struct Themes {
+    themes: Vec<Theme>,
+}
+
+impl Themes {
+    /// 玩家自定义主题所在的目录
+    pub fn dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("snake").join("themes"))
+    }
+
+    /// 读取内置主题和自定义主题，同名的自定义主题会覆盖内置主题
+    pub fn load() -> Self {
+        let mut themes: Vec<Theme> = BUILTIN_THEMES
+            .iter()
+            .map(|content| toml::from_str(content).expect("Error parsing built-in theme"))
+            .collect();
+
+        let entries = match Themes::dir().map(fs::read_dir) {
+            Some(Ok(entries)) => entries,
+            _ => return Themes { themes },
+        };
+
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "toml") {
+                continue;
+            }
+
+            let theme: Theme = match fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| toml::from_str(&content).map_err(|e| e.to_string()))
+            {
+                Ok(theme) => theme,
+                Err(e) => {
+                    eprintln!("Ignoring invalid theme {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            match themes.iter_mut().find(|other| other.name == theme.name) {
+                Some(other) => *other = theme,
+                None => themes.push(theme),
+            }
+        }
+
+        Themes { themes }
+    }
+
+    /// 按名称查找主题，找不到时使用第一个内置主题
+    pub fn get(&self, name: &str) -> &Theme {
+        self.themes
+            .iter()
+            .find(|theme| theme.name == name)
+            .unwrap_or(&self.themes[0])
+    }
+
+    /// 从名为`name`的主题开始，向前或向后切换`step`个主题
+    pub fn cycle(&self, name: &str, step: i32) -> &Theme {
+        let len = self.themes.len() as i32;
+        let index = self
+            .themes
+            .iter()
+            .position(|theme| theme.name == name)
+            .unwrap_or(0) as i32;
+
+        &self.themes[((index + step) % len + len) as usize % self.themes.len()]
+    }
+}
@@ -0,0 +1,195 @@
+use crate::Block;
+use graphics::{Context, Image, ImageSize, Transformed};
+use opengl_graphics::{GlGraphics, Texture, TextureSettings};
+
+/// 图块集中的图块，按在图片中从左到右的顺序排列
+/// 蛇头朝右，直线段水平，拐角连接右边和下边，蛇尾连接右边
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Sprite {
+    Head,
+    Straight,
+    Corner,
+    Tail,
+    Fruit,
+    Wall,
+    PowerUp,
+}
+
+/// 从一张图片中切分出来的图块集
+pub struct Tileset {
+    texture: Texture,
+    tile_size: f64,
+}
+
+impl Tileset {
+    /// 从图片文件的内容创建图块集，图块是正方形，边长等于图片的高度
+    /// 必须在OpenGL上下文创建之后调用
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|e| e.to_string())?
+            .to_rgba8();
+        let texture = Texture::from_image(&image, &TextureSettings::new());
+        let tile_size = texture.get_height() as f64;
+
+        Ok(Tileset { texture, tile_size })
+    }
+
+    /// 在`rect`中绘制旋转`angle`度并用`color`染色的图块
+    pub fn draw(
+        &self,
+        sprite: Sprite,
+        angle: f64,
+        color: [f32; 4],
+        rect: [f64; 4],
+        c: &Context,
+        gl: &mut GlGraphics,
+    ) {
+        let src = [sprite as usize as f64 * self.tile_size, 0.0, self.tile_size, self.tile_size];
+        let transform = c
+            .transform
+            .trans(rect[0] + rect[2] * 0.5, rect[1] + rect[3] * 0.5)
+            .rot_deg(angle)
+            .trans(-rect[2] * 0.5, -rect[3] * 0.5);
+
+        Image::new_color(color)
+            .src_rect(src)
+            .rect([0.0, 0.0, rect[2], rect[3]])
+            .draw(&self.texture, &c.draw_state, transform, gl);
+    }
+}
+
+/// 从`from`指向相邻的`to`的方向角，向右为0度，顺时针增加
+fn side(from: &Block, to: &Block) -> i32 {
+    match (to.pos_x - from.pos_x, to.pos_y - from.pos_y) {
+        (dx, _) if dx > 0 => 0,
+        (_, dy) if dy > 0 => 90,
+        (dx, _) if dx < 0 => 180,
+        _ => 270,
+    }
+}
+
+/// 两块是否上下左右相邻
+fn adjacent(a: &Block, b: &Block) -> bool {
+    (a.pos_x - b.pos_x).abs() + (a.pos_y - b.pos_y).abs() == 1
+}
+
+/// 根据前后相邻的块为蛇的一段选择图块和旋转角度
+/// `prev`是靠近蛇头的一块，`next`是靠近蛇尾的一块
+/// 穿过传送门的地方前后两块不相邻，这一侧按直线延伸绘制，看起来像是从传送门中穿过
+pub fn snake_sprite(block: &Block, prev: Option<&Block>, next: Option<&Block>) -> (Sprite, f64) {
+    let prev_side = prev
+        .filter(|prev| adjacent(block, prev))
+        .map(|prev| side(block, prev));
+    let next_side = next
+        .filter(|next| adjacent(block, next))
+        .map(|next| side(block, next));
+    let (prev_side, next_side) = match (prev.is_some(), prev_side, next.is_some(), next_side) {
+        (true, None, _, Some(b)) => (Some((b + 180) % 360), Some(b)),
+        (_, Some(a), true, None) => (Some(a), Some((a + 180) % 360)),
+        _ => (prev_side, next_side),
+    };
+
+    match (prev_side, next_side) {
+        // 蛇头朝着远离脖子的方向
+        (None, Some(b)) => (Sprite::Head, ((b + 180) % 360) as f64),
+        (None, None) => (Sprite::Head, 0.0),
+        // 蛇尾连接前一块
+        (Some(a), None) => (Sprite::Tail, a as f64),
+        (Some(a), Some(b)) => {
+            if (a - b).abs() == 180 {
+                (Sprite::Straight, (a % 180) as f64)
+            } else if (b - a + 360) % 360 == 90 {
+                // 拐角图块连接0度和90度，旋转后连接a和a+90
+                (Sprite::Corner, a as f64)
+            } else {
+                (Sprite::Corner, b as f64)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::Collited;
+
+    fn block(x: i32, y: i32) -> Block {
+        Block::new(x, y, Collited::WithSnake, [1.0; 4])
+    }
+
+    #[test]
+    fn embedded_tileset_has_a_tile_for_every_sprite() {
+        let bytes = crate::assets::embedded(crate::consts::DEFAULT_TILESET).unwrap();
+        let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+
+        assert_eq!(image.width() % image.height(), 0);
+        assert!((image.width() / image.height()) as usize > Sprite::PowerUp as usize);
+    }
+
+    #[test]
+    fn head_faces_away_from_the_neck() {
+        let head = block(5, 5);
+
+        assert_eq!(
+            snake_sprite(&head, None, Some(&block(6, 5))),
+            (Sprite::Head, 180.0)
+        );
+        assert_eq!(
+            snake_sprite(&head, None, Some(&block(5, 4))),
+            (Sprite::Head, 90.0)
+        );
+        assert_eq!(snake_sprite(&head, None, None), (Sprite::Head, 0.0));
+    }
+
+    #[test]
+    fn tail_points_to_the_previous_block() {
+        assert_eq!(
+            snake_sprite(&block(5, 5), Some(&block(5, 4)), None),
+            (Sprite::Tail, 270.0)
+        );
+        assert_eq!(
+            snake_sprite(&block(5, 5), Some(&block(4, 5)), None),
+            (Sprite::Tail, 180.0)
+        );
+    }
+
+    #[test]
+    fn straight_and_corner_pieces() {
+        let middle = block(5, 5);
+
+        assert_eq!(
+            snake_sprite(&middle, Some(&block(4, 5)), Some(&block(6, 5))),
+            (Sprite::Straight, 0.0)
+        );
+        assert_eq!(
+            snake_sprite(&middle, Some(&block(5, 4)), Some(&block(5, 6))),
+            (Sprite::Straight, 90.0)
+        );
+        // 连接右边和下边的拐角不需要旋转，与经过的顺序无关
+        assert_eq!(
+            snake_sprite(&middle, Some(&block(6, 5)), Some(&block(5, 6))),
+            (Sprite::Corner, 0.0)
+        );
+        assert_eq!(
+            snake_sprite(&middle, Some(&block(5, 6)), Some(&block(6, 5))),
+            (Sprite::Corner, 0.0)
+        );
+        // 连接上边和左边的拐角
+        assert_eq!(
+            snake_sprite(&middle, Some(&block(5, 4)), Some(&block(4, 5))),
+            (Sprite::Corner, 180.0)
+        );
+    }
+
+    #[test]
+    fn portal_side_continues_straight() {
+        assert_eq!(
+            snake_sprite(&block(5, 5), Some(&block(30, 20)), Some(&block(6, 5))),
+            (Sprite::Straight, 0.0)
+        );
+        assert_eq!(
+            snake_sprite(&block(5, 5), Some(&block(5, 4)), Some(&block(30, 20))),
+            (Sprite::Straight, 90.0)
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{consts, game::Game, Block, Direction, GameMode, GameStatus};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute, queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+/// 在终端中运行游戏，方向键或WASD控制，空格暂停，R重新开始，Q或Esc退出
+/// 移动空间按终端的尺寸决定，最大与窗口中默认的移动空间相同
+pub fn run(seed: u64, mode: GameMode) -> io::Result<()> {
+    let (columns, rows) = terminal::size()?;
+
+    // 边框占用最右一列和最下一行，最后一行用来显示分数
+    let circus = [
+        (columns as u32)
+            .saturating_sub(1)
+            .min(consts::TUI_MAX_SIZE[0]),
+        (rows as u32).saturating_sub(2).min(consts::TUI_MAX_SIZE[1]),
+    ];
+    if circus[0] < consts::TUI_MIN_SIZE[0] || circus[1] < consts::TUI_MIN_SIZE[1] {
+        return Err(io::Error::other(format!(
+            "terminal is too small, need at least {}x{}",
+            consts::TUI_MIN_SIZE[0] + 1,
+            consts::TUI_MIN_SIZE[1] + 2
+        )));
+    }
+
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    // 无论游戏如何结束都要恢复终端
+    let result = play(&mut stdout, circus, seed, mode);
+
+    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+
+    result
+}
+
+// 游戏的主循环，等待按键的同时按经过的时间推进游戏，局面变化时重新绘制
+fn play(stdout: &mut Stdout, circus: [u32; 2], seed: u64, mode: GameMode) -> io::Result<()> {
+    let frame = Duration::from_millis(consts::TUI_FRAME_MILLIS);
+    let mut game = Game::new(mode, circus, seed);
+    let mut paused = false;
+    let mut last_update = Instant::now();
+
+    draw(stdout, &game, paused)?;
+    loop {
+        let mut changed = false;
+
+        if event::poll(frame)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                match key.code {
+                    KeyCode::Up | KeyCode::Char('w') => {
+                        game.turn(Direction::Up);
+                    }
+                    KeyCode::Down | KeyCode::Char('s') => {
+                        game.turn(Direction::Down);
+                    }
+                    KeyCode::Left | KeyCode::Char('a') => {
+                        game.turn(Direction::Left);
+                    }
+                    KeyCode::Right | KeyCode::Char('d') => {
+                        game.turn(Direction::Right);
+                    }
+                    KeyCode::Char(' ') => paused = !paused,
+                    KeyCode::Char('r') if game.status == GameStatus::GAMEOVER => {
+                        game = Game::new(mode, circus, rand::random());
+                        paused = false;
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    _ => (),
+                }
+                changed = true;
+            }
+        }
+
+        // 暂停时不计时
+        let now = Instant::now();
+        if !paused {
+            game.update(now.duration_since(last_update).as_secs_f64());
+        }
+        last_update = now;
+
+        // 终端界面只关心局面是否变化，不需要分发事件
+        if !game.take_events().is_empty() || changed {
+            draw(stdout, &game, paused)?;
+        }
+    }
+}
+
+// 用字符画出整个局面和状态栏
+fn draw(stdout: &mut Stdout, game: &Game, paused: bool) -> io::Result<()> {
+    queue!(stdout, terminal::Clear(ClearType::All))?;
+
+    let bricks = game
+        .board_wall
+        .bricks()
+        .iter()
+        .chain(game.walls.iter().flat_map(|wall| wall.bricks().iter()));
+    for brick in bricks {
+        put(stdout, brick, '#')?;
+    }
+    // 动态墙壁不会被撞上时画成虚线
+    for dynamic in game.dynamic_walls.iter() {
+        let symbol = match dynamic.solid {
+            true => '#',
+            false => ':',
+        };
+        for brick in dynamic.wall.bricks() {
+            put(stdout, brick, symbol)?;
+        }
+    }
+    for portal in game.portals.iter() {
+        for end in portal.ends.iter() {
+            put(stdout, end, 'O')?;
+        }
+    }
+    if let Some(power_up) = &game.power_up {
+        put(stdout, &power_up.block, '+')?;
+    }
+    put(stdout, game.fruit.block(), '*')?;
+    for block in game.snake.body() {
+        put(stdout, block, 'o')?;
+    }
+    put(stdout, game.snake.head(), '@')?;
+
+    let status = match (game.status == GameStatus::GAMEOVER, paused) {
+        (true, _) => format!("Game over! Score {}  R restart  Q quit", game.score),
+        (false, true) => format!("Paused  Score {}  Space resume  Q quit", game.score),
+        (false, false) => format!(
+            "{}  Score {}  Length {}  Time {:.0}s  Space pause  Q quit",
+            game.mode.name(),
+            game.score,
+            game.snake.length(),
+            game.elapsed
+        ),
+    };
+    queue!(
+        stdout,
+        cursor::MoveTo(0, game.circus[1] as u16 + 1),
+        Print(status)
+    )?;
+
+    stdout.flush()
+}
+
+// 在块所在的格子上画一个字符
+fn put(stdout: &mut Stdout, block: &Block, symbol: char) -> io::Result<()> {
+    let (x, y) = block.cell();
+
+    queue!(stdout, cursor::MoveTo(x as u16, y as u16), Print(symbol))
+}